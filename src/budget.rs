@@ -0,0 +1,697 @@
+/*!
+ * Copyright (c) 2026 mingcheng <mingcheng@apache.org>
+ *
+ * This source code is licensed under the MIT License,
+ * which is located in the LICENSE file in the source tree's root directory.
+ *
+ * Token budgeting of the prompt.
+ *
+ * Token counts are estimated per model family by splitting the text the way
+ * BPE pre-tokenizers do (letter runs, digit groups, punctuation, whitespace)
+ * and charging each piece by the family's typical token length, which is
+ * close enough to budget with and needs no vocabulary files.
+ *
+ * When the rendered prompt exceeds the budget the diff is trimmed in
+ * stages until it fits: hunk bodies first (low-signal files and the largest
+ * hunks before anything else), then whole hunk lists, then whole files.
+ * File and hunk headers survive as long as possible so the model still sees
+ * the shape of the change, and everything left out is listed in the prompt.
+ *
+ * File: budget.rs
+ * Author: mingcheng <mingcheng@apache.org>
+ * File Created: 2026-10-16 20:14:37
+ *
+ * Modified By: mingcheng <mingcheng@apache.org>
+ * Last Modified: 2026-10-16 20:14:37
+ */
+
+use crate::config::Config;
+use crate::git::diff::{self, FileDiff, Line};
+use crate::prompt;
+use std::cmp::Reverse;
+use std::error::Error;
+use tracing::{debug, info, warn};
+
+/// Upper bound of the default budget, so a huge context window does not
+/// turn every large commit into an expensive request.
+pub const DEFAULT_MAX_PROMPT_TOKENS: usize = 100_000;
+
+/// Tokens kept free for the answer when `max_completion_tokens` is unset.
+const RESPONSE_RESERVE: usize = 4096;
+
+/// Tokens kept free for the list of omitted changes.
+const NOTE_RESERVE: usize = 64;
+
+/// Fewest body lines worth keeping of a partially trimmed hunk.
+const MIN_KEPT_LINES: usize = 3;
+
+/// Path fragments of vendored, generated and data files, whose hunks are
+/// trimmed before anything else.
+const LOW_SIGNAL_DIRS: &[&str] = &[
+    "vendor/",
+    "node_modules/",
+    "third_party/",
+    "dist/",
+    "generated/",
+    "__snapshots__/",
+];
+
+/// File name suffixes of generated, minified and data files.
+const LOW_SIGNAL_SUFFIXES: &[&str] = &[
+    ".lock", ".min.js", ".min.css", ".map", ".svg", ".snap", ".pb.go", "_pb2.py", ".csv", ".ipynb",
+];
+
+/// Lines longer than this on average mark a hunk as minified or data.
+const LONG_LINE: usize = 200;
+
+/// Tokenizer family of a model, deciding how text is counted and how large
+/// the context window is assumed to be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Family {
+    /// `o200k_base`: GPT-4o, GPT-4.1, GPT-5 and the o-series.
+    O200k,
+    /// `cl100k_base`: GPT-4 and GPT-3.5.
+    Cl100k,
+    Claude,
+    Gemini,
+    /// Llama, Qwen, Mistral and anything unknown, counted conservatively.
+    Other,
+}
+
+impl Family {
+    /// Family of `model`, ignoring a `vendor/` prefix as used by gateways.
+    pub fn of(model: &str) -> Self {
+        let model = model
+            .rsplit('/')
+            .next()
+            .unwrap_or(model)
+            .to_ascii_lowercase();
+        let starts = |prefixes: &[&str]| prefixes.iter().any(|p| model.starts_with(p));
+        if starts(&[
+            "gpt-4o", "gpt-4.1", "gpt-5", "gpt-oss", "chatgpt", "o1", "o3", "o4",
+        ]) {
+            Self::O200k
+        } else if starts(&["gpt-4", "gpt-3.5"]) {
+            Self::Cl100k
+        } else if starts(&["claude"]) {
+            Self::Claude
+        } else if starts(&["gemini", "gemma"]) {
+            Self::Gemini
+        } else {
+            Self::Other
+        }
+    }
+
+    /// Assumed context window in tokens.
+    pub fn context_window(self) -> usize {
+        match self {
+            Self::O200k => 128_000,
+            Self::Cl100k => 16_384,
+            Self::Claude => 200_000,
+            Self::Gemini => 1_048_576,
+            Self::Other => 32_768,
+        }
+    }
+
+    /// Average letters per token of an English or identifier word.
+    fn word_chars(self) -> usize {
+        match self {
+            Self::O200k => 6,
+            Self::Cl100k | Self::Gemini => 5,
+            Self::Claude | Self::Other => 4,
+        }
+    }
+
+    /// Digits merged into one token.
+    fn digit_group(self) -> usize {
+        match self {
+            Self::O200k | Self::Cl100k | Self::Claude => 3,
+            Self::Gemini | Self::Other => 1,
+        }
+    }
+
+    /// Estimated number of tokens in `text`.
+    pub fn estimate(self, text: &str) -> usize {
+        let mut tokens = 0;
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            let mut run = |same: fn(&char) -> bool| {
+                let mut len: usize = 1;
+                while chars.next_if(same).is_some() {
+                    len += 1;
+                }
+                len
+            };
+            tokens += if c.is_ascii_alphabetic() {
+                run(char::is_ascii_alphabetic).div_ceil(self.word_chars())
+            } else if c.is_ascii_digit() {
+                run(char::is_ascii_digit).div_ceil(self.digit_group())
+            } else if c == ' ' {
+                // A single space merges into the piece that follows it.
+                let len = run(|c| *c == ' ');
+                usize::from(len > 1 || chars.peek().is_none_or(|c| c.is_whitespace()))
+            } else if c.is_ascii_whitespace() {
+                run(char::is_ascii_whitespace);
+                1
+            } else if c.is_ascii() {
+                run(char::is_ascii_punctuation).div_ceil(2)
+            } else {
+                1
+            };
+        }
+        tokens
+    }
+}
+
+/// The diff as it goes into the prompt, with a note for every file that
+/// was trimmed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Fitted {
+    pub diff: Vec<String>,
+    pub omitted: Vec<String>,
+    /// Whether trimming hunk bodies was not enough, so whole hunks or files
+    /// were dropped or the prompt is still over budget.
+    pub overflow: bool,
+}
+
+/// Prompt token budget of a model.
+pub struct Budget {
+    family: Family,
+    /// `None` disables trimming.
+    max_tokens: Option<usize>,
+}
+
+impl Budget {
+    /// Budget for the configured model. `max_prompt_tokens` overrides the
+    /// family default and `0` disables trimming.
+    pub fn new(config: &Config) -> Self {
+        let family = Family::of(&config.model);
+        let max_tokens = match config.max_prompt_tokens {
+            Some(0) => None,
+            Some(max) => Some(max),
+            None => {
+                let reserve = config
+                    .max_completion_tokens
+                    .map_or(RESPONSE_RESERVE, |n| n as usize);
+                Some(
+                    family
+                        .context_window()
+                        .saturating_sub(reserve)
+                        .min(DEFAULT_MAX_PROMPT_TOKENS),
+                )
+            }
+        };
+        Self { family, max_tokens }
+    }
+
+    /// Estimated number of tokens in `text`.
+    pub fn estimate(&self, text: &str) -> usize {
+        self.family.estimate(text)
+    }
+
+    /// Render `files` as a diff trimmed so that the prompt fits the budget.
+    ///
+    /// The prompt context, such as the logs and the diffstat, is always
+    /// sent in full.
+    pub fn fit(
+        &self,
+        system: &str,
+        context: &prompt::Context,
+        files: &[FileDiff],
+    ) -> Result<Fitted, Box<dyn Error>> {
+        let unchanged = Fitted {
+            diff: diff::render(files),
+            ..Fitted::default()
+        };
+        let Some(max_tokens) = self.max_tokens else {
+            return Ok(unchanged);
+        };
+
+        let overhead = self.estimate(system) + self.estimate(&prompt::user(context, &[], &[])?);
+        let diff_tokens = self.lines_cost(&unchanged.diff);
+        debug!(
+            "estimated prompt size: {} tokens, budget {max_tokens}",
+            overhead + diff_tokens
+        );
+        if overhead + diff_tokens <= max_tokens {
+            return Ok(unchanged);
+        }
+
+        let available = max_tokens.saturating_sub(overhead + NOTE_RESERVE);
+        info!(
+            "prompt is about {} tokens, over the {max_tokens} token budget; trimming the diff",
+            overhead + diff_tokens
+        );
+        let mut files: Vec<Trimmed> = files.iter().map(Trimmed::new).collect();
+        let mut overflow = false;
+        'stages: for stage in [Stage::HunkBodies, Stage::HunkLists, Stage::Files] {
+            loop {
+                let cost = self.files_cost(&files);
+                if cost <= available {
+                    break 'stages;
+                }
+                if !self.trim(&mut files, stage, cost - available) {
+                    break;
+                }
+                overflow |= !matches!(stage, Stage::HunkBodies);
+            }
+        }
+
+        let mut fitted = render(&files);
+        let total = overhead + self.lines_cost(&fitted.diff) + self.lines_cost(&fitted.omitted);
+        if total > max_tokens {
+            warn!("prompt is still about {total} tokens after trimming the diff");
+            overflow = true;
+        }
+        fitted.overflow = overflow;
+        Ok(fitted)
+    }
+
+    fn lines_cost(&self, lines: &[String]) -> usize {
+        lines.iter().map(|l| self.estimate(l) + 1).sum()
+    }
+
+    /// Cost of one file on its own, including its omission note.
+    fn file_cost(&self, file: &Trimmed) -> usize {
+        self.files_cost(std::slice::from_ref(file))
+    }
+
+    fn files_cost(&self, files: &[Trimmed]) -> usize {
+        let fitted = render(files);
+        self.lines_cost(&fitted.diff) + self.lines_cost(&fitted.omitted)
+    }
+
+    /// Cut roughly `excess` tokens at the given stage, lowest signal and
+    /// largest first. Returns whether anything was cut.
+    fn trim(&self, files: &mut [Trimmed], stage: Stage, mut excess: usize) -> bool {
+        let mut changed = false;
+        match stage {
+            Stage::HunkBodies => {
+                let mut hunks: Vec<(usize, usize, bool, usize)> = Vec::new();
+                for (f, file) in files.iter().enumerate() {
+                    for (h, hunk) in file.hunks.iter().enumerate() {
+                        let low = file.low_signal || hunk.is_minified();
+                        let cost = self.lines_cost(hunk.kept_lines());
+                        if cost > 0 {
+                            hunks.push((f, h, low, cost));
+                        }
+                    }
+                }
+                hunks.sort_by_key(|&(f, h, low, cost)| (Reverse(low), Reverse(cost), f, h));
+
+                for (f, h, _, cost) in hunks {
+                    if excess == 0 {
+                        break;
+                    }
+                    let hunk = &mut files[f].hunks[h];
+                    // Keep the start of the hunk when dropping all of it
+                    // would cut much more than needed.
+                    let keep_budget = cost.saturating_sub(excess + MARKER_COST);
+                    let mut kept = 0;
+                    let mut kept_cost = 0;
+                    for line in hunk.kept_lines() {
+                        let line_cost = self.estimate(line) + 1;
+                        if kept_cost + line_cost > keep_budget {
+                            break;
+                        }
+                        kept += 1;
+                        kept_cost += line_cost;
+                    }
+                    if kept < MIN_KEPT_LINES {
+                        kept = 0;
+                        kept_cost = 0;
+                    }
+                    if kept == hunk.kept_lines().len() {
+                        continue;
+                    }
+                    hunk.kept = kept;
+                    excess = excess.saturating_sub(cost - kept_cost - MARKER_COST.min(cost));
+                    changed = true;
+                }
+            }
+            Stage::HunkLists => {
+                let mut order: Vec<usize> = (0..files.len()).collect();
+                order.sort_by_key(|&f| {
+                    let file = &files[f];
+                    (Reverse(file.low_signal), Reverse(file.hunks.len()), f)
+                });
+                for f in order {
+                    if excess == 0 {
+                        break;
+                    }
+                    let file = &mut files[f];
+                    if file.hunks.is_empty() || file.hunks_dropped {
+                        continue;
+                    }
+                    let before = self.file_cost(file);
+                    file.hunks_dropped = true;
+                    changed = true;
+                    excess = excess.saturating_sub(before.saturating_sub(self.file_cost(file)));
+                }
+            }
+            Stage::Files => {
+                // Later files go first, the order of a diff is alphabetical
+                // and carries no priority of its own.
+                for file in files.iter_mut().rev() {
+                    if excess == 0 {
+                        break;
+                    }
+                    if file.dropped {
+                        continue;
+                    }
+                    let before = self.file_cost(file);
+                    file.dropped = true;
+                    changed = true;
+                    excess = excess.saturating_sub(before.saturating_sub(self.file_cost(file)));
+                }
+            }
+        }
+        changed
+    }
+}
+
+/// Rough cost of an omission marker line.
+const MARKER_COST: usize = 8;
+
+#[derive(Debug, Clone, Copy)]
+enum Stage {
+    HunkBodies,
+    HunkLists,
+    Files,
+}
+
+/// Trimming state of one file of the diff.
+#[derive(Debug)]
+struct Trimmed {
+    path: String,
+    /// Lines before the first hunk, which are never trimmed.
+    header: Vec<String>,
+    hunks: Vec<TrimmedHunk>,
+    low_signal: bool,
+    hunks_dropped: bool,
+    dropped: bool,
+}
+
+impl Trimmed {
+    /// The untrimmed state of `file`.
+    fn new(file: &FileDiff) -> Self {
+        let mut header = Vec::new();
+        file.render_header(&mut header);
+        let hunks = if file.shows_hunks() {
+            file.hunks
+                .iter()
+                .map(|hunk| TrimmedHunk {
+                    header: hunk.header.clone(),
+                    body: hunk.lines.iter().map(Line::to_string).collect(),
+                    kept: usize::MAX,
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Self {
+            path: file.path().to_string(),
+            low_signal: is_low_signal(file.path()),
+            header,
+            hunks,
+            hunks_dropped: false,
+            dropped: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TrimmedHunk {
+    header: String,
+    body: Vec<String>,
+    /// Leading body lines kept in the prompt.
+    kept: usize,
+}
+
+impl TrimmedHunk {
+    fn kept_lines(&self) -> &[String] {
+        &self.body[..self.kept.min(self.body.len())]
+    }
+
+    fn is_minified(&self) -> bool {
+        !self.body.is_empty()
+            && self.body.iter().map(String::len).sum::<usize>() / self.body.len() > LONG_LINE
+    }
+}
+
+/// Whether `path` is a vendored, generated or data file.
+fn is_low_signal(path: &str) -> bool {
+    let path = path.to_ascii_lowercase();
+    LOW_SIGNAL_DIRS
+        .iter()
+        .any(|dir| path.starts_with(dir) || path.contains(&format!("/{dir}")))
+        || LOW_SIGNAL_SUFFIXES.iter().any(|s| path.ends_with(s))
+}
+
+/// Lines of the trimmed diff with inline markers, and the omission notes.
+///
+/// Files without any hunk left are listed together, as one line each would
+/// cost more than their headers did.
+fn render(files: &[Trimmed]) -> Fitted {
+    let mut fitted = Fitted::default();
+    let mut hunks_dropped = Vec::new();
+    let mut dropped = Vec::new();
+    for file in files {
+        let lines: usize = file.hunks.iter().map(|h| h.body.len()).sum();
+        let name = if file.path.is_empty() {
+            "(unknown file)"
+        } else {
+            &file.path
+        };
+        if file.dropped {
+            dropped.push(name);
+            continue;
+        }
+        fitted.diff.extend(file.header.iter().cloned());
+        if file.hunks_dropped {
+            fitted.diff.push(format!(
+                "[... {} hunks with {lines} lines omitted ...]",
+                file.hunks.len()
+            ));
+            hunks_dropped.push(name);
+            continue;
+        }
+
+        let mut trimmed_hunks = 0;
+        let mut omitted_lines = 0;
+        for hunk in &file.hunks {
+            fitted.diff.push(hunk.header.clone());
+            let kept = hunk.kept_lines();
+            fitted.diff.extend(kept.iter().cloned());
+            let dropped = hunk.body.len() - kept.len();
+            if dropped > 0 {
+                trimmed_hunks += 1;
+                omitted_lines += dropped;
+                fitted
+                    .diff
+                    .push(format!("[... {dropped} lines omitted ...]"));
+            }
+        }
+        if trimmed_hunks > 0 {
+            fitted.omitted.push(format!(
+                "- {name}: {trimmed_hunks} of {} hunks trimmed ({omitted_lines} lines)",
+                file.hunks.len()
+            ));
+        }
+    }
+    if !hunks_dropped.is_empty() {
+        fitted.omitted.push(format!(
+            "- all hunks omitted, headers kept: {}",
+            hunks_dropped.join(", ")
+        ));
+    }
+    if !dropped.is_empty() {
+        fitted
+            .omitted
+            .push(format!("- files omitted entirely: {}", dropped.join(", ")));
+    }
+    fitted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::diff::Hunk;
+
+    fn budget(max_tokens: usize) -> Budget {
+        Budget {
+            family: Family::O200k,
+            max_tokens: Some(max_tokens),
+        }
+    }
+
+    fn file(path: &str, hunks: &[(usize, &str)]) -> FileDiff {
+        let hunks = hunks
+            .iter()
+            .enumerate()
+            .map(|(i, (len, text))| Hunk {
+                header: format!("@@ -{i},{len} +{i},{len} @@"),
+                old_start: i as u32,
+                old_lines: *len as u32,
+                new_start: i as u32,
+                new_lines: *len as u32,
+                lines: (0..*len)
+                    .map(|n| Line {
+                        origin: '+',
+                        content: format!("{text} {n}"),
+                    })
+                    .collect(),
+            })
+            .collect();
+        FileDiff {
+            status: git2::Delta::Modified,
+            old_path: path.into(),
+            new_path: path.into(),
+            old_oid: git2::Oid::zero(),
+            new_oid: git2::Oid::zero(),
+            old_mode: 0o100644,
+            new_mode: 0o100644,
+            binary: false,
+            hunks,
+            summary: None,
+        }
+    }
+
+    #[test]
+    fn detects_model_families() {
+        assert_eq!(Family::of("gpt-5-mini"), Family::O200k);
+        assert_eq!(Family::of("openai/o3"), Family::O200k);
+        assert_eq!(Family::of("gpt-4-turbo"), Family::Cl100k);
+        assert_eq!(Family::of("claude-sonnet-4-5"), Family::Claude);
+        assert_eq!(Family::of("models/gemini-2.5-pro"), Family::Gemini);
+        assert_eq!(Family::of("qwen3:8b"), Family::Other);
+    }
+
+    #[test]
+    fn estimates_like_a_bpe_tokenizer() {
+        let family = Family::O200k;
+        assert_eq!(family.estimate(""), 0);
+        assert_eq!(family.estimate("hello world"), 2);
+        assert_eq!(family.estimate("let x = 12345;"), 6);
+        assert_eq!(family.estimate("    return"), 2);
+        assert_eq!(family.estimate("提交信息"), 4);
+        // Conservative families count the same text as more tokens.
+        let code = "fn estimate(self, text: &str) -> usize {";
+        assert!(Family::Other.estimate(code) >= family.estimate(code));
+    }
+
+    #[test]
+    fn default_budget_leaves_room_for_the_answer() {
+        let config = Config {
+            model: "qwen3:8b".into(),
+            max_completion_tokens: Some(1000),
+            ..Config::default()
+        };
+        assert_eq!(Budget::new(&config).max_tokens, Some(31_768));
+
+        let config = Config {
+            model: "claude-sonnet-4-5".into(),
+            ..Config::default()
+        };
+        assert_eq!(
+            Budget::new(&config).max_tokens,
+            Some(DEFAULT_MAX_PROMPT_TOKENS)
+        );
+
+        let config = Config {
+            max_prompt_tokens: Some(0),
+            ..Config::default()
+        };
+        assert_eq!(Budget::new(&config).max_tokens, None);
+    }
+
+    #[test]
+    fn small_diffs_are_unchanged() {
+        let files = [file("src/main.rs", &[(3, "small change")])];
+        let fitted = budget(10_000)
+            .fit("sys", &prompt::Context::default(), &files)
+            .unwrap();
+        assert_eq!(fitted.diff, diff::render(&files));
+        assert!(fitted.omitted.is_empty());
+        assert!(!fitted.overflow);
+    }
+
+    #[test]
+    fn low_signal_and_large_hunks_are_trimmed_first() {
+        let diff = [
+            file("src/lib.rs", &[(5, "important logic change")]),
+            file("vendor/dep/mod.rs", &[(400, "vendored code line")]),
+            file("src/big.rs", &[(300, "a large but relevant edit")]),
+        ];
+
+        let fitted = budget(2_000)
+            .fit("sys", &prompt::Context::default(), &diff)
+            .unwrap();
+        let text = fitted.diff.join("\n");
+        // Headers survive and the small, relevant hunk is intact.
+        assert!(text.contains("diff --git a/vendor/dep/mod.rs b/vendor/dep/mod.rs"));
+        assert!(text.contains("@@ -0,400 +0,400 @@"));
+        assert!(text.contains("+important logic change 4"));
+        assert!(text.contains("[... 400 lines omitted ...]"));
+        assert!(!text.contains("vendored code line"));
+        assert_eq!(fitted.omitted.len(), 2);
+        assert_eq!(
+            fitted.omitted[0],
+            "- vendor/dep/mod.rs: 1 of 1 hunks trimmed (400 lines)"
+        );
+        assert!(fitted.omitted[1].starts_with("- src/big.rs: 1 of 1 hunks trimmed"));
+        // The large relevant hunk keeps its beginning.
+        assert!(text.contains("+a large but relevant edit 0"));
+        assert!(!fitted.overflow);
+
+        let total = Family::O200k.estimate(
+            &prompt::user(&prompt::Context::default(), &fitted.diff, &fitted.omitted).unwrap(),
+        );
+        assert!(total <= 2_000, "{total}");
+    }
+
+    #[test]
+    fn drops_whole_files_when_headers_do_not_fit() {
+        let diff: Vec<FileDiff> = (0..200)
+            .map(|i| file(&format!("src/module_{i}.rs"), &[(20, "changed line")]))
+            .collect();
+
+        let fitted = budget(3_000)
+            .fit("sys", &prompt::Context::default(), &diff)
+            .unwrap();
+        assert!(fitted.diff[0].starts_with("diff --git a/src/module_0.rs"));
+        assert!(fitted.overflow);
+        let notes = fitted.omitted.join("\n");
+        assert!(
+            notes.contains("- files omitted entirely: ") && notes.ends_with("src/module_199.rs"),
+            "{notes}"
+        );
+        assert!(notes.contains("- all hunks omitted, headers kept: src/module_0.rs"));
+    }
+
+    #[test]
+    fn names_files_by_their_model_path() {
+        let mut summarized = file("Cargo.lock", &[(400, "checksum line")]);
+        summarized.summary = Some(diff::Summary {
+            kind: diff::SummaryKind::Dependencies,
+            lines: vec!["serde 1.0.200 → 1.0.210".into()],
+        });
+        let files = [file("docs/a b/x.md", &[(400, "a sentence")]), summarized];
+
+        let fitted = budget(1_000)
+            .fit("sys", &prompt::Context::default(), &files)
+            .unwrap();
+        assert_eq!(fitted.omitted.len(), 1);
+        assert!(
+            fitted.omitted[0].starts_with("- docs/a b/x.md: 1 of 1 hunks trimmed"),
+            "{:?}",
+            fitted.omitted
+        );
+        // Summaries replace the hunks and are never trimmed.
+        assert!(
+            fitted
+                .diff
+                .ends_with(&["serde 1.0.200 → 1.0.210".to_string()])
+        );
+    }
+}
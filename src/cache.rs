@@ -7,7 +7,8 @@
  * Lightweight on-disk cache for OpenAI responses.
  *
  * The cache key is derived from the inputs that influence the API request
- * (model name, system prompt, staged diff and recent commit logs). When the
+ * (model name, generation parameters, system prompt, staged diff and recent
+ * commit logs). When the
  * staged diff and the surrounding context have not changed, the previously
  * generated commit message can be reused without contacting the API.
  *
@@ -19,6 +20,7 @@
  * Last Modified: 2026-05-07 11:44:53
  */
 
+use crate::provider::Params;
 use std::error::Error;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -49,6 +51,7 @@ pub fn new(git_dir: &Path) -> Self {
     /// regardless of the standard library's hashing implementation.
     pub fn build_key(
         model: &str,
+        params: &Params,
         system_prompt: &str,
         diffs: &[String],
         logs: &[String],
@@ -56,6 +59,10 @@ pub fn build_key(
         let mut hasher = Fnv1a64::new();
         hasher.write(model.as_bytes());
         hasher.write(b"\0");
+        // Field order is fixed, so the JSON form is canonical.
+        let params = serde_json::to_string(params).unwrap_or_default();
+        hasher.write(params.as_bytes());
+        hasher.write(b"\0");
         hasher.write(system_prompt.as_bytes());
         hasher.write(b"\0");
         for d in diffs {
@@ -171,17 +178,33 @@ mod tests {
     fn build_key_is_stable_and_sensitive() {
         let diffs = vec!["diff --git a/x b/x".to_string(), "+hello".to_string()];
         let logs = vec!["initial commit".to_string()];
-        let k1 = Cache::build_key("gpt-5", "sys", &diffs, &logs);
-        let k2 = Cache::build_key("gpt-5", "sys", &diffs, &logs);
+        let params = Params::default();
+        let k1 = Cache::build_key("gpt-5", &params, "sys", &diffs, &logs);
+        let k2 = Cache::build_key("gpt-5", &params, "sys", &diffs, &logs);
         assert_eq!(k1, k2);
 
-        let k3 = Cache::build_key("gpt-5", "sys", &diffs, &["other".to_string()]);
+        let k3 = Cache::build_key("gpt-5", &params, "sys", &diffs, &["other".to_string()]);
         assert_ne!(k1, k3);
 
-        let k4 = Cache::build_key("gpt-4", "sys", &diffs, &logs);
+        let k4 = Cache::build_key("gpt-4", &params, "sys", &diffs, &logs);
         assert_ne!(k1, k4);
     }
 
+    #[test]
+    fn build_key_changes_with_params() {
+        let base = Cache::build_key("gpt-5", &Params::default(), "sys", &[], &[]);
+        let warmer = Params {
+            temperature: Some(0.7),
+            ..Params::default()
+        };
+        assert_ne!(base, Cache::build_key("gpt-5", &warmer, "sys", &[], &[]));
+        let stopped = Params {
+            stop: vec!["\n\n".to_string()],
+            ..Params::default()
+        };
+        assert_ne!(base, Cache::build_key("gpt-5", &stopped, "sys", &[], &[]));
+    }
+
     #[test]
     fn get_returns_none_for_missing_entry() {
         let tmp =
@@ -207,7 +230,7 @@ fn clear_removes_entries_and_returns_count() {
 
     #[test]
     fn build_key_format_is_16_hex_chars() {
-        let k = Cache::build_key("m", "s", &[], &[]);
+        let k = Cache::build_key("m", &Params::default(), "s", &[], &[]);
         assert_eq!(k.len(), 16);
         assert!(k.chars().all(|c| c.is_ascii_hexdigit()));
     }
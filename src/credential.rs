@@ -0,0 +1,176 @@
+/*!
+ * Copyright (c) 2026 mingcheng <mingcheng@apache.org>
+ *
+ * This source code is licensed under the MIT License,
+ * which is located in the LICENSE file in the source tree's root directory.
+ *
+ * API token retrieval from external sources.
+ *
+ * Similar to git's credential helpers, the token can be produced by a
+ * command (e.g. `pass show openai`, `op read op://vault/openai/token`) or
+ * read from a file that only the current user can access. Command output is
+ * cached for the lifetime of the process so helpers that prompt or hit a
+ * remote vault run at most once.
+ *
+ * File: credential.rs
+ * Author: mingcheng <mingcheng@apache.org>
+ * File Created: 2026-10-16 14:02:18
+ *
+ * Modified By: mingcheng <mingcheng@apache.org>
+ * Last Modified: 2026-10-16 14:02:18
+ */
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::{LazyLock, Mutex};
+use tracing::{debug, trace};
+
+/// Tokens already produced by a command, keyed by the command line.
+static COMMAND_CACHE: LazyLock<Mutex<HashMap<String, String>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Run `command` through the platform shell and return the first non-empty
+/// line of its standard output as the token.
+///
+/// Standard error is captured and included in the error message when the
+/// command fails, so helper diagnostics are not lost inside git hooks.
+pub fn from_command(command: &str) -> Result<String, Box<dyn Error>> {
+    if let Some(token) = COMMAND_CACHE
+        .lock()
+        .map_err(|_| "token cache poisoned")?
+        .get(command)
+    {
+        trace!("reusing cached token from command");
+        return Ok(token.clone());
+    }
+
+    debug!("running token command `{command}`");
+    let output = shell(command)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("failed to run token command `{command}`: {e}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "token command `{command}` failed with {}: {}",
+            output.status,
+            stderr.trim()
+        )
+        .into());
+    }
+
+    let stdout = String::from_utf8(output.stdout)
+        .map_err(|_| format!("token command `{command}` printed non UTF-8 output"))?;
+    let token =
+        first_line(&stdout).ok_or_else(|| format!("token command `{command}` printed nothing"))?;
+
+    COMMAND_CACHE
+        .lock()
+        .map_err(|_| "token cache poisoned")?
+        .insert(command.to_string(), token.clone());
+    Ok(token)
+}
+
+/// Read the token from `path`.
+///
+/// On Unix the file must be a regular file that is not accessible by group
+/// or others (like `~/.ssh` keys), otherwise it is rejected.
+pub fn from_file(path: &Path) -> Result<String, Box<dyn Error>> {
+    let metadata = fs::metadata(path)
+        .map_err(|e| format!("failed to access token file {}: {e}", path.display()))?;
+    if !metadata.is_file() {
+        return Err(format!("token file {} is not a regular file", path.display()).into());
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = metadata.permissions().mode() & 0o777;
+        if mode & 0o077 != 0 {
+            return Err(format!(
+                "token file {} is accessible by other users (mode {mode:o}); run `chmod 600` on it",
+                path.display()
+            )
+            .into());
+        }
+    }
+
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("failed to read token file {}: {e}", path.display()))?;
+    first_line(&content).ok_or_else(|| format!("token file {} is empty", path.display()).into())
+}
+
+fn first_line(text: &str) -> Option<String> {
+    text.lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn command_output_is_trimmed_and_cached() {
+        let counter =
+            std::env::temp_dir().join(format!("aigitcommit-token-count-{}", std::process::id()));
+        let _ = fs::remove_file(&counter);
+        let command = format!(
+            "echo run >> {}; printf '\\n  sk-test  \\nignored\\n'",
+            counter.display()
+        );
+
+        assert_eq!(from_command(&command).unwrap(), "sk-test");
+        assert_eq!(from_command(&command).unwrap(), "sk-test");
+        let runs = fs::read_to_string(&counter).unwrap();
+        assert_eq!(runs.lines().count(), 1, "command should run only once");
+        let _ = fs::remove_file(counter);
+    }
+
+    #[test]
+    fn failing_command_reports_status_and_stderr() {
+        let err = from_command("echo denied >&2; exit 3")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("exit status: 3"), "{err}");
+        assert!(err.contains("denied"), "{err}");
+
+        let err = from_command("true").unwrap_err().to_string();
+        assert!(err.contains("printed nothing"), "{err}");
+    }
+
+    #[test]
+    fn token_file_requires_private_permissions() {
+        let path =
+            std::env::temp_dir().join(format!("aigitcommit-token-file-{}", std::process::id()));
+        fs::write(&path, "sk-file\n").unwrap();
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        let err = from_file(&path).unwrap_err().to_string();
+        assert!(err.contains("chmod 600"), "{err}");
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        assert_eq!(from_file(&path).unwrap(), "sk-file");
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,443 @@
+/*!
+ * Copyright (c) 2026 mingcheng <mingcheng@apache.org>
+ *
+ * This source code is licensed under the MIT License,
+ * which is located in the LICENSE file in the source tree's root directory.
+ *
+ * Dependency summaries of lock files and manifests.
+ *
+ * The raw diff of a lock file is long and says little, so both staged
+ * versions are parsed instead and the prompt gets the packages that were
+ * added, removed or moved to another version, e.g.
+ * `serde 1.0.200 → 1.0.210`.
+ *
+ * File: deps.rs
+ * Author: mingcheng <mingcheng@apache.org>
+ * File Created: 2026-10-17 13:26:40
+ *
+ * Modified By: mingcheng <mingcheng@apache.org>
+ * Last Modified: 2026-10-17 13:26:40
+ */
+
+use serde_json::Value as Json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// Changed packages listed per file; the rest are counted.
+const MAX_CHANGES: usize = 50;
+
+/// Resolved versions of every package.
+type Versions = BTreeMap<String, BTreeSet<String>>;
+
+/// Supported lock file and manifest formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// `[[package]]` tables with `name` and `version`: Cargo, Poetry and uv.
+    TomlPackages,
+    NpmLock,
+    YarnLock,
+    PnpmLock,
+    ComposerLock,
+    GemfileLock,
+    GoSum,
+    GoMod,
+}
+
+impl Format {
+    fn of(path: &str) -> Option<Self> {
+        let name = Path::new(path).file_name()?.to_str()?;
+        Some(match name {
+            "Cargo.lock" | "poetry.lock" | "uv.lock" => Self::TomlPackages,
+            "package-lock.json" | "npm-shrinkwrap.json" => Self::NpmLock,
+            "yarn.lock" => Self::YarnLock,
+            "pnpm-lock.yaml" => Self::PnpmLock,
+            "composer.lock" => Self::ComposerLock,
+            "Gemfile.lock" => Self::GemfileLock,
+            "go.sum" => Self::GoSum,
+            "go.mod" => Self::GoMod,
+            _ => return None,
+        })
+    }
+
+    /// Versions in `text`; `None` when it cannot be parsed.
+    fn parse(self, text: &str) -> Option<Versions> {
+        let mut versions = Versions::new();
+        let mut add = |name: &str, version: &str| {
+            if !name.is_empty() && !version.is_empty() {
+                versions
+                    .entry(name.to_string())
+                    .or_default()
+                    .insert(version.to_string());
+            }
+        };
+
+        match self {
+            Self::TomlPackages => {
+                let table: toml::Table = text.parse().ok()?;
+                let packages = table.get("package").and_then(|p| p.as_array());
+                for package in packages.into_iter().flatten() {
+                    let field = |key| package.get(key).and_then(|v| v.as_str());
+                    add(
+                        field("name").unwrap_or_default(),
+                        field("version").unwrap_or_default(),
+                    );
+                }
+            }
+            Self::NpmLock => {
+                let json: Json = serde_json::from_str(text).ok()?;
+                if let Some(packages) = json["packages"].as_object() {
+                    // lockfileVersion 2 and 3: keyed by install path.
+                    for (path, package) in packages {
+                        if path.is_empty() || package["link"].as_bool() == Some(true) {
+                            continue;
+                        }
+                        let name = package["name"].as_str().unwrap_or_else(|| {
+                            path.rsplit_once("node_modules/").map_or(path, |(_, n)| n)
+                        });
+                        add(name, package["version"].as_str().unwrap_or_default());
+                    }
+                } else {
+                    // lockfileVersion 1: nested `dependencies` maps.
+                    let mut pending = vec![&json["dependencies"]];
+                    while let Some(deps) = pending.pop() {
+                        let Some(deps) = deps.as_object() else {
+                            continue;
+                        };
+                        for (name, package) in deps {
+                            add(name, package["version"].as_str().unwrap_or_default());
+                            pending.push(&package["dependencies"]);
+                        }
+                    }
+                }
+            }
+            Self::YarnLock => {
+                let mut names = Vec::new();
+                for line in text.lines() {
+                    if line.starts_with('#') || line.trim().is_empty() {
+                        continue;
+                    }
+                    if !line.starts_with(' ') {
+                        // `"a@^1.0.0", a@^1.1.0:` in v1, `"a@npm:^1.0.0":` in berry.
+                        names = line
+                            .trim_end_matches(':')
+                            .split(", ")
+                            .filter_map(|spec| {
+                                let spec = spec.trim_matches('"');
+                                spec.rfind('@').filter(|&i| i > 0).map(|i| &spec[..i])
+                            })
+                            .filter(|name| *name != "__metadata")
+                            .collect();
+                        names.dedup();
+                    } else if let Some(version) = line
+                        .trim()
+                        .strip_prefix("version")
+                        .filter(|v| v.starts_with([' ', ':']))
+                    {
+                        let version = version.trim_start_matches(':').trim().trim_matches('"');
+                        for name in &names {
+                            add(name, version);
+                        }
+                    }
+                }
+            }
+            Self::PnpmLock => {
+                let mut in_packages = false;
+                for line in text.lines() {
+                    if !line.starts_with(' ') && !line.trim().is_empty() {
+                        in_packages = line == "packages:";
+                        continue;
+                    }
+                    let Some(key) = line.strip_prefix("  ").filter(|k| !k.starts_with(' ')) else {
+                        continue;
+                    };
+                    if !in_packages {
+                        continue;
+                    }
+                    // `/a@1.0.0(peer@2.0.0):`, `a@1.0.0:`, or `/a/1.0.0_peer@2.0.0:` in v5.
+                    let key = key.trim_end_matches(':').trim_matches(['\'', '"']);
+                    let key = key.strip_prefix('/').unwrap_or(key);
+                    let key = key.split('(').next().unwrap_or(key);
+                    let is_version = |v: &&str| v.starts_with(|c: char| c.is_ascii_digit());
+                    let split = key
+                        .rsplit_once('/')
+                        .filter(|(_, v)| is_version(v))
+                        .or_else(|| {
+                            let i = key.get(1..)?.rfind('@')? + 1;
+                            Some((&key[..i], &key[i + 1..]))
+                        });
+                    if let Some((name, version)) = split {
+                        add(name, version.split('_').next().unwrap_or(version));
+                    }
+                }
+            }
+            Self::ComposerLock => {
+                let json: Json = serde_json::from_str(text).ok()?;
+                for section in ["packages", "packages-dev"] {
+                    for package in json[section].as_array().into_iter().flatten() {
+                        add(
+                            package["name"].as_str().unwrap_or_default(),
+                            package["version"].as_str().unwrap_or_default(),
+                        );
+                    }
+                }
+            }
+            Self::GemfileLock => {
+                // Resolved gems are indented by four spaces: `    rack (3.0.8)`.
+                for line in text.lines() {
+                    let Some(spec) = line.strip_prefix("    ").filter(|s| !s.starts_with(' '))
+                    else {
+                        continue;
+                    };
+                    if let Some((name, version)) = spec.split_once(" (") {
+                        add(name, version.trim_end_matches(')'));
+                    }
+                }
+            }
+            Self::GoSum => {
+                for line in text.lines() {
+                    let mut fields = line.split_whitespace();
+                    if let (Some(module), Some(version)) = (fields.next(), fields.next()) {
+                        add(module, version.trim_end_matches("/go.mod"));
+                    }
+                }
+            }
+            Self::GoMod => {
+                let mut in_block = false;
+                for line in text.lines() {
+                    let line = line.split("//").next().unwrap_or_default().trim();
+                    let spec = if in_block {
+                        if line == ")" {
+                            in_block = false;
+                            continue;
+                        }
+                        line
+                    } else if line == "require (" {
+                        in_block = true;
+                        continue;
+                    } else if let Some(spec) = line.strip_prefix("require ") {
+                        spec
+                    } else {
+                        continue;
+                    };
+                    let mut fields = spec.split_whitespace();
+                    if let (Some(module), Some(version)) = (fields.next(), fields.next()) {
+                        add(module, version);
+                    }
+                }
+            }
+        }
+        Some(versions)
+    }
+}
+
+/// Whether `path` is a lock file or manifest with a dependency summary.
+pub fn is_supported(path: &str) -> bool {
+    Format::of(path).is_some()
+}
+
+/// Dependency changes between the `old` and `new` content of `path`, one
+/// line per package.
+///
+/// `None` when the format is unsupported or either side cannot be parsed,
+/// so the caller can fall back to the raw diff.
+pub fn summarize(path: &str, old: &str, new: &str) -> Option<Vec<String>> {
+    let format = Format::of(path)?;
+    // An added or deleted file has no content on one side.
+    let parse = |text: &str| {
+        if text.trim().is_empty() {
+            Some(Versions::new())
+        } else {
+            format.parse(text)
+        }
+    };
+    let (old, new) = (parse(old)?, parse(new)?);
+
+    let empty = BTreeSet::new();
+    let names: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    let mut changes = Vec::new();
+    for name in names {
+        let before = old.get(name).unwrap_or(&empty);
+        let after = new.get(name).unwrap_or(&empty);
+        let removed = join(before.difference(after));
+        let added = join(after.difference(before));
+        match (removed.is_empty(), added.is_empty()) {
+            (true, true) => {}
+            (true, false) => changes.push(format!("added {name} {added}")),
+            (false, true) => changes.push(format!("removed {name} {removed}")),
+            (false, false) => changes.push(format!("{name} {removed} → {added}")),
+        }
+    }
+
+    if changes.is_empty() {
+        return Some(vec!["no dependency versions changed".to_string()]);
+    }
+    if changes.len() > MAX_CHANGES {
+        let more = changes.len() - MAX_CHANGES;
+        changes.truncate(MAX_CHANGES);
+        changes.push(format!("… and {more} more"));
+    }
+    Some(changes)
+}
+
+fn join<'a>(versions: impl Iterator<Item = &'a String>) -> String {
+    versions.map(String::as_str).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_cargo_lock() {
+        let old = r#"
+version = 4
+
+[[package]]
+name = "serde"
+version = "1.0.200"
+
+[[package]]
+name = "rand"
+version = "0.7.3"
+
+[[package]]
+name = "rand"
+version = "0.8.5"
+
+[[package]]
+name = "log"
+version = "0.4.20"
+"#;
+        let new = r#"
+version = 4
+
+[[package]]
+name = "serde"
+version = "1.0.210"
+
+[[package]]
+name = "rand"
+version = "0.8.5"
+
+[[package]]
+name = "tokio-util"
+version = "0.7.16"
+
+[[package]]
+name = "log"
+version = "0.4.20"
+"#;
+        assert_eq!(
+            summarize("crates/x/Cargo.lock", old, new).unwrap(),
+            [
+                "removed rand 0.7.3",
+                "serde 1.0.200 → 1.0.210",
+                "added tokio-util 0.7.16",
+            ]
+        );
+        assert_eq!(
+            summarize("Cargo.lock", old, old).unwrap(),
+            ["no dependency versions changed"]
+        );
+    }
+
+    #[test]
+    fn summarizes_npm_and_yarn_locks() {
+        let old = r#"{"lockfileVersion":3,"packages":{
+            "":{"name":"app"},
+            "node_modules/react":{"version":"18.2.0"},
+            "node_modules/a/node_modules/@types/node":{"version":"20.1.0"}}}"#;
+        let new = r#"{"lockfileVersion":3,"packages":{
+            "":{"name":"app"},
+            "node_modules/react":{"version":"18.3.1"},
+            "node_modules/a/node_modules/@types/node":{"version":"20.1.0"}}}"#;
+        assert_eq!(
+            summarize("package-lock.json", old, new).unwrap(),
+            ["react 18.2.0 → 18.3.1"]
+        );
+
+        let old = "# yarn lockfile v1\n\n\"@babel/core@^7.0.0\", \"@babel/core@^7.1.0\":\n  version \"7.1.0\"\n  resolved \"https://x\"\n";
+        let new = "__metadata:\n  version: 6\n\n\"@babel/core@npm:^7.0.0\":\n  version: 7.2.0\n";
+        assert_eq!(
+            summarize("yarn.lock", old, new).unwrap(),
+            ["@babel/core 7.1.0 → 7.2.0"]
+        );
+    }
+
+    #[test]
+    fn summarizes_pnpm_lock() {
+        let old = "lockfileVersion: '6.0'\n\npackages:\n\n  /@scope/a@1.0.0(react@18.0.0):\n    resolution: {integrity: x}\n\n  /b/2.0.0_c@1.0.0:\n    dev: false\n";
+        let new = "lockfileVersion: '9.0'\n\npackages:\n\n  '@scope/a@1.1.0':\n    resolution: {integrity: x}\n\n  b@2.0.0:\n    resolution: {integrity: y}\n";
+        assert_eq!(
+            summarize("pnpm-lock.yaml", old, new).unwrap(),
+            ["@scope/a 1.0.0 → 1.1.0"]
+        );
+    }
+
+    #[test]
+    fn summarizes_go_modules() {
+        let old = "module x\n\nrequire (\n\tgithub.com/a/b v1.2.0\n\tgolang.org/x/net v0.1.0 // indirect\n)\n";
+        let new = "module x\n\nrequire (\n\tgithub.com/a/b v1.3.0\n)\n\nrequire golang.org/x/sync v0.5.0\n";
+        assert_eq!(
+            summarize("go.mod", old, new).unwrap(),
+            [
+                "github.com/a/b v1.2.0 → v1.3.0",
+                "removed golang.org/x/net v0.1.0",
+                "added golang.org/x/sync v0.5.0",
+            ]
+        );
+
+        let old = "github.com/a/b v1.2.0 h1:x=\ngithub.com/a/b v1.2.0/go.mod h1:y=\n";
+        let new = "github.com/a/b v1.3.0 h1:z=\ngithub.com/a/b v1.3.0/go.mod h1:w=\n";
+        assert_eq!(
+            summarize("go.sum", old, new).unwrap(),
+            ["github.com/a/b v1.2.0 → v1.3.0"]
+        );
+    }
+
+    #[test]
+    fn summarizes_composer_and_gemfile_locks() {
+        let old =
+            r#"{"packages":[{"name":"monolog/monolog","version":"3.4.0"}],"packages-dev":[]}"#;
+        let new =
+            r#"{"packages":[{"name":"monolog/monolog","version":"3.5.0"}],"packages-dev":[]}"#;
+        assert_eq!(
+            summarize("composer.lock", old, new).unwrap(),
+            ["monolog/monolog 3.4.0 → 3.5.0"]
+        );
+
+        let old = "GEM\n  remote: https://rubygems.org/\n  specs:\n    rack (3.0.8)\n      base64\n\nDEPENDENCIES\n  rack\n";
+        let new = "GEM\n  remote: https://rubygems.org/\n  specs:\n    rack (3.1.0)\n      base64\n\nDEPENDENCIES\n  rack\n";
+        assert_eq!(
+            summarize("Gemfile.lock", old, new).unwrap(),
+            ["rack 3.0.8 → 3.1.0"]
+        );
+    }
+
+    #[test]
+    fn unsupported_or_unparsable_files_fall_back() {
+        assert!(summarize("src/main.rs", "", "").is_none());
+        assert!(summarize("package-lock.json", "{", "{}").is_none());
+        assert_eq!(
+            summarize(
+                "package-lock.json",
+                "",
+                r#"{"packages":{"node_modules/a":{"version":"1.0.0"}}}"#
+            )
+            .unwrap(),
+            ["added a 1.0.0"]
+        );
+        assert!(is_supported("web/yarn.lock"));
+        assert!(!is_supported("Cargo.toml"));
+    }
+
+    #[test]
+    fn long_summaries_are_capped() {
+        let new: String = (0..60)
+            .map(|i| format!("[[package]]\nname = \"crate-{i:02}\"\nversion = \"1.0.0\"\n\n"))
+            .collect();
+        let summary = summarize("Cargo.lock", "", &new).unwrap();
+        assert_eq!(summary.len(), MAX_CHANGES + 1);
+        assert_eq!(summary[MAX_CHANGES], "… and 10 more");
+    }
+}
@@ -0,0 +1,608 @@
+/*!
+ * Copyright (c) 2026 mingcheng <mingcheng@apache.org>
+ *
+ * This source code is licensed under the MIT License,
+ * which is located in the LICENSE file in the source tree's root directory.
+ *
+ * Structured model of a diff and its unified-diff rendering.
+ *
+ * File: diff.rs
+ * Author: mingcheng <mingcheng@apache.org>
+ * File Created: 2026-10-17 09:12:26
+ *
+ * Modified By: mingcheng <mingcheng@apache.org>
+ * Last Modified: 2026-10-17 09:12:26
+ */
+
+use crate::config::DEFAULT_RENAME_THRESHOLD;
+use crate::git::exclude::Excluded;
+use git2::{Delta, DiffLineType, Oid, Patch};
+use std::fmt::{Display, Formatter};
+
+/// Abbreviated object id length used in `index` lines, as git does by default.
+const ABBREV_LEN: usize = 7;
+
+/// Which changes a diff describes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Scope {
+    /// The index against HEAD, like `git diff --cached`.
+    #[default]
+    Staged,
+    /// The working tree against the index, like `git diff`.
+    Unstaged { untracked: bool },
+    /// The working tree against HEAD, like `git diff HEAD`.
+    All { untracked: bool },
+    /// The index against the parent of HEAD: what HEAD records once amended.
+    Amend,
+    /// A commit against its first parent, like `git show`.
+    Revision(String),
+    /// The tree of one commit against another, given as `A..B`, or against
+    /// their merge base as `A...B`.
+    Range(String),
+}
+
+impl Scope {
+    /// Whether the new side of the diff is the working tree.
+    pub fn workdir(&self) -> bool {
+        matches!(self, Self::Unstaged { .. } | Self::All { .. })
+    }
+
+    /// Whether untracked files are included.
+    pub fn untracked(&self) -> bool {
+        matches!(
+            self,
+            Self::Unstaged { untracked: true } | Self::All { untracked: true }
+        )
+    }
+
+    /// One sentence telling the model what the diff covers.
+    pub fn describe(&self) -> String {
+        let untracked = if self.untracked() {
+            ", including untracked files"
+        } else {
+            ""
+        };
+        match self {
+            Self::Staged => "Staged changes: the index against HEAD.".to_string(),
+            Self::Unstaged { .. } => {
+                format!("Unstaged changes: the working tree against the index{untracked}.")
+            }
+            Self::All { .. } => {
+                format!("All uncommitted changes: the working tree against HEAD{untracked}.")
+            }
+            Self::Amend => "The changes HEAD records once amended: the index against the \
+                parent of HEAD."
+                .to_string(),
+            Self::Revision(revision) => {
+                format!("The changes of commit `{revision}` against its first parent.")
+            }
+            Self::Range(range) => format!("The changes of the commit range `{range}`."),
+        }
+    }
+}
+
+/// What a diff covers and how it is computed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffSpec {
+    pub scope: Scope,
+    /// Git pathspecs limiting the diff; empty for the whole tree.
+    pub pathspecs: Vec<String>,
+    /// Gitignore-syntax patterns excluded on top of the defaults.
+    pub excludes: Vec<String>,
+    /// Similarity percentage for rename and copy detection, `0` to disable.
+    pub rename_threshold: u16,
+}
+
+impl Default for DiffSpec {
+    fn default() -> Self {
+        Self {
+            scope: Scope::default(),
+            pathspecs: Vec::new(),
+            excludes: Vec::new(),
+            rename_threshold: DEFAULT_RENAME_THRESHOLD,
+        }
+    }
+}
+
+/// The files of a diff and the paths left out of it.
+#[derive(Debug, Default)]
+pub struct Changes {
+    pub files: Vec<FileDiff>,
+    pub excluded: Vec<Excluded>,
+}
+
+/// One line of a hunk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    /// `' '` for context, `'+'` for additions, `'-'` for deletions and `'\\'`
+    /// for a "No newline at end of file" marker.
+    pub origin: char,
+    /// Line text without its origin and trailing newline; whitespace is kept.
+    pub content: String,
+}
+
+/// A hunk of changed lines with its surrounding context.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hunk {
+    /// The `@@ -a,b +c,d @@ section` header.
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<Line>,
+}
+
+impl Display for Line {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.origin, self.content)
+    }
+}
+
+impl Hunk {
+    /// Append the header and the lines of this hunk to `out`.
+    pub fn render(&self, out: &mut Vec<String>) {
+        out.push(self.header.clone());
+        out.extend(self.lines.iter().map(Line::to_string));
+    }
+}
+
+/// Where the lines shown instead of the hunks of a file come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SummaryKind {
+    /// Package version changes of a lock file or manifest.
+    Dependencies,
+    /// A model-written summary of a file too large for the prompt.
+    Model,
+}
+
+/// Lines shown instead of the hunks of a file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub kind: SummaryKind,
+    pub lines: Vec<String>,
+}
+
+/// The changes of a single file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileDiff {
+    pub status: Delta,
+    pub old_path: String,
+    pub new_path: String,
+    /// Full blob ids; zero on the missing side of an addition or deletion.
+    pub old_oid: Oid,
+    pub new_oid: Oid,
+    pub old_mode: u32,
+    pub new_mode: u32,
+    pub binary: bool,
+    pub hunks: Vec<Hunk>,
+    /// Lines rendered instead of the hunks, such as the dependency changes
+    /// of a lock file.
+    pub summary: Option<Summary>,
+}
+
+impl FileDiff {
+    /// Build the model from a libgit2 patch of one delta.
+    pub fn from_patch(patch: &Patch) -> Result<Self, git2::Error> {
+        let delta = patch.delta();
+        let path = |file: git2::DiffFile| {
+            file.path()
+                .map(|p| p.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        };
+
+        let mut hunks = Vec::with_capacity(patch.num_hunks());
+        for h in 0..patch.num_hunks() {
+            let (hunk, count) = patch.hunk(h)?;
+            let mut lines = Vec::with_capacity(count);
+            for l in 0..count {
+                let line = patch.line_in_hunk(h, l)?;
+                let origin = match line.origin_value() {
+                    DiffLineType::Addition => '+',
+                    DiffLineType::Deletion => '-',
+                    DiffLineType::ContextEOFNL
+                    | DiffLineType::AddEOFNL
+                    | DiffLineType::DeleteEOFNL => {
+                        lines.push(Line {
+                            origin: '\\',
+                            content: " No newline at end of file".to_string(),
+                        });
+                        continue;
+                    }
+                    _ => ' ',
+                };
+                let content = String::from_utf8_lossy(line.content());
+                let content = content.strip_suffix('\n').unwrap_or(&content);
+                lines.push(Line {
+                    origin,
+                    content: content.to_string(),
+                });
+            }
+            hunks.push(Hunk {
+                header: String::from_utf8_lossy(hunk.header())
+                    .trim_end()
+                    .to_string(),
+                old_start: hunk.old_start(),
+                old_lines: hunk.old_lines(),
+                new_start: hunk.new_start(),
+                new_lines: hunk.new_lines(),
+                lines,
+            });
+        }
+
+        Ok(Self {
+            status: delta.status(),
+            old_path: path(delta.old_file()),
+            new_path: path(delta.new_file()),
+            old_oid: delta.old_file().id(),
+            new_oid: delta.new_file().id(),
+            old_mode: u32::from(delta.old_file().mode()),
+            new_mode: u32::from(delta.new_file().mode()),
+            binary: delta.flags().is_binary(),
+            hunks,
+            summary: None,
+        })
+    }
+
+    /// The path the change is known by: the old one for deletions.
+    pub fn path(&self) -> &str {
+        if self.status == Delta::Deleted {
+            &self.old_path
+        } else {
+            &self.new_path
+        }
+    }
+
+    /// One-letter status as shown by `git diff --name-status`.
+    pub fn status_letter(&self) -> char {
+        match self.status {
+            Delta::Added => 'A',
+            Delta::Deleted => 'D',
+            Delta::Modified => 'M',
+            Delta::Renamed => 'R',
+            Delta::Copied => 'C',
+            Delta::Typechange => 'T',
+            _ => 'X',
+        }
+    }
+
+    /// The path for display, `old => new` for renames and copies.
+    pub fn display_path(&self) -> String {
+        match self.status {
+            Delta::Renamed | Delta::Copied => format!("{} => {}", self.old_path, self.new_path),
+            _ => self.path().to_string(),
+        }
+    }
+
+    /// Number of added lines.
+    pub fn additions(&self) -> usize {
+        self.count('+')
+    }
+
+    /// Number of deleted lines.
+    pub fn deletions(&self) -> usize {
+        self.count('-')
+    }
+
+    fn count(&self, origin: char) -> usize {
+        self.hunks
+            .iter()
+            .flat_map(|h| &h.lines)
+            .filter(|l| l.origin == origin)
+            .count()
+    }
+
+    /// Whether the hunks are rendered, rather than a binary marker or a
+    /// summary.
+    pub fn shows_hunks(&self) -> bool {
+        !self.binary && self.summary.is_none()
+    }
+
+    /// Number of diff lines in the hunks, counting each hunk header.
+    pub fn diff_lines(&self) -> usize {
+        self.hunks.iter().map(|h| h.lines.len() + 1).sum()
+    }
+
+    /// Append the unified-diff lines of this file to `out`.
+    pub fn render(&self, out: &mut Vec<String>) {
+        self.render_header(out);
+        if self.shows_hunks() {
+            for hunk in &self.hunks {
+                hunk.render(out);
+            }
+        }
+    }
+
+    /// Append the lines before the first hunk to `out`. Binary files and
+    /// summaries consist of nothing else.
+    pub fn render_header(&self, out: &mut Vec<String>) {
+        out.push(format!(
+            "diff --git a/{} b/{}",
+            self.old_path, self.new_path
+        ));
+        match self.status {
+            Delta::Added => out.push(format!("new file mode {:o}", self.new_mode)),
+            Delta::Deleted => out.push(format!("deleted file mode {:o}", self.old_mode)),
+            _ if self.old_mode != self.new_mode => {
+                out.push(format!("old mode {:o}", self.old_mode));
+                out.push(format!("new mode {:o}", self.new_mode));
+            }
+            _ => {}
+        }
+        match self.status {
+            Delta::Renamed => {
+                out.push(format!("rename from {}", self.old_path));
+                out.push(format!("rename to {}", self.new_path));
+            }
+            Delta::Copied => {
+                out.push(format!("copy from {}", self.old_path));
+                out.push(format!("copy to {}", self.new_path));
+            }
+            _ => {}
+        }
+
+        let (old, new) = (abbrev(self.old_oid), abbrev(self.new_oid));
+        match self.status {
+            Delta::Added | Delta::Deleted => out.push(format!("index {old}..{new}")),
+            // Pure renames and mode changes carry no content change.
+            _ if self.old_oid == self.new_oid => {}
+            _ if self.old_mode == self.new_mode => {
+                out.push(format!("index {old}..{new} {:o}", self.new_mode))
+            }
+            _ => out.push(format!("index {old}..{new}")),
+        }
+
+        let old_side = match self.status {
+            Delta::Added => "/dev/null".to_string(),
+            _ => format!("a/{}", self.old_path),
+        };
+        let new_side = match self.status {
+            Delta::Deleted => "/dev/null".to_string(),
+            _ => format!("b/{}", self.new_path),
+        };
+        if self.binary {
+            out.push(format!("Binary files {old_side} and {new_side} differ"));
+            return;
+        }
+        if let Some(summary) = &self.summary {
+            out.push(match summary.kind {
+                SummaryKind::Dependencies => format!(
+                    "[dependency changes, {} diff lines not shown]",
+                    self.diff_lines()
+                ),
+                SummaryKind::Model => format!("[summary of {} diff lines]", self.diff_lines()),
+            });
+            out.extend(summary.lines.iter().cloned());
+            return;
+        }
+        if self.hunks.is_empty() {
+            return;
+        }
+        out.push(format!("--- {old_side}"));
+        out.push(format!("+++ {new_side}"));
+    }
+}
+
+/// Render files as unified-diff lines.
+pub fn render(files: &[FileDiff]) -> Vec<String> {
+    let mut out = Vec::new();
+    for file in files {
+        file.render(&mut out);
+    }
+    out
+}
+
+/// A `git diff --stat`-like summary: one line per file with its status,
+/// path and line counts, followed by the totals.
+pub fn stat(files: &[FileDiff]) -> Vec<String> {
+    if files.is_empty() {
+        return Vec::new();
+    }
+    let paths: Vec<String> = files.iter().map(FileDiff::display_path).collect();
+    let width = paths.iter().map(|p| p.chars().count()).max().unwrap_or(0);
+
+    let mut out = Vec::with_capacity(files.len() + 1);
+    let (mut additions, mut deletions) = (0, 0);
+    for (file, path) in files.iter().zip(&paths) {
+        let counts = if file.binary {
+            "binary".to_string()
+        } else {
+            additions += file.additions();
+            deletions += file.deletions();
+            format!("+{} -{}", file.additions(), file.deletions())
+        };
+        out.push(format!(
+            "{} {path:<width$} | {counts}",
+            file.status_letter()
+        ));
+    }
+    out.push(format!(
+        "{} file{} changed, {additions} insertion{}(+), {deletions} deletion{}(-)",
+        files.len(),
+        plural(files.len()),
+        plural(additions),
+        plural(deletions),
+    ));
+    out
+}
+
+fn plural(n: usize) -> &'static str {
+    if n == 1 { "" } else { "s" }
+}
+
+fn abbrev(oid: Oid) -> String {
+    let mut hex = oid.to_string();
+    hex.truncate(ABBREV_LEN);
+    hex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_scopes() {
+        assert_eq!(
+            Scope::Staged.describe(),
+            "Staged changes: the index against HEAD."
+        );
+        assert_eq!(
+            Scope::Unstaged { untracked: true }.describe(),
+            "Unstaged changes: the working tree against the index, including untracked files."
+        );
+        assert!(
+            !Scope::All { untracked: false }
+                .describe()
+                .contains("untracked")
+        );
+        assert!(
+            Scope::Range("v1..v2".into())
+                .describe()
+                .contains("`v1..v2`")
+        );
+    }
+
+    fn modified() -> FileDiff {
+        FileDiff {
+            status: Delta::Modified,
+            old_path: "src/lib.rs".into(),
+            new_path: "src/lib.rs".into(),
+            old_oid: Oid::from_str("1111111111111111111111111111111111111111").unwrap(),
+            new_oid: Oid::from_str("2222222222222222222222222222222222222222").unwrap(),
+            old_mode: 0o100644,
+            new_mode: 0o100644,
+            binary: false,
+            summary: None,
+            hunks: vec![Hunk {
+                header: "@@ -1,2 +1,2 @@ fn main() {".into(),
+                old_start: 1,
+                old_lines: 2,
+                new_start: 1,
+                new_lines: 2,
+                lines: vec![
+                    Line {
+                        origin: ' ',
+                        content: "".into(),
+                    },
+                    Line {
+                        origin: '-',
+                        content: "    let a = 1;".into(),
+                    },
+                    Line {
+                        origin: '+',
+                        content: "    let a = 2;".into(),
+                    },
+                    Line {
+                        origin: '\\',
+                        content: " No newline at end of file".into(),
+                    },
+                ],
+            }],
+        }
+    }
+
+    #[test]
+    fn renders_unified_diff() {
+        assert_eq!(
+            render(&[modified()]),
+            [
+                "diff --git a/src/lib.rs b/src/lib.rs",
+                "index 1111111..2222222 100644",
+                "--- a/src/lib.rs",
+                "+++ b/src/lib.rs",
+                "@@ -1,2 +1,2 @@ fn main() {",
+                " ",
+                "-    let a = 1;",
+                "+    let a = 2;",
+                "\\ No newline at end of file",
+            ]
+        );
+        assert_eq!((modified().additions(), modified().deletions()), (1, 1));
+    }
+
+    #[test]
+    fn renders_additions_deletions_and_binaries() {
+        let mut added = modified();
+        added.status = Delta::Added;
+        added.old_oid = Oid::zero();
+        added.old_mode = 0;
+        let lines = render(&[added]);
+        assert_eq!(lines[1], "new file mode 100644");
+        assert_eq!(lines[2], "index 0000000..2222222");
+        assert_eq!(lines[3], "--- /dev/null");
+
+        let mut deleted = modified();
+        deleted.status = Delta::Deleted;
+        deleted.binary = true;
+        deleted.hunks.clear();
+        assert_eq!(deleted.path(), "src/lib.rs");
+        assert_eq!(
+            render(&[deleted])[3],
+            "Binary files a/src/lib.rs and /dev/null differ"
+        );
+    }
+
+    #[test]
+    fn renders_summary_instead_of_hunks() {
+        let mut lock = modified();
+        lock.summary = Some(Summary {
+            kind: SummaryKind::Dependencies,
+            lines: vec!["serde 1.0.200 → 1.0.210".into()],
+        });
+        assert_eq!(
+            render(std::slice::from_ref(&lock))[2..],
+            [
+                "[dependency changes, 5 diff lines not shown]",
+                "serde 1.0.200 → 1.0.210",
+            ]
+        );
+
+        lock.summary = Some(Summary {
+            kind: SummaryKind::Model,
+            lines: vec!["- bumps serde".into()],
+        });
+        assert_eq!(
+            render(&[lock])[2..],
+            ["[summary of 5 diff lines]", "- bumps serde"]
+        );
+    }
+
+    #[test]
+    fn renders_renames() {
+        let mut renamed = modified();
+        renamed.status = Delta::Renamed;
+        renamed.new_path = "src/core.rs".into();
+        renamed.new_oid = renamed.old_oid;
+        renamed.hunks.clear();
+        assert_eq!(
+            render(&[renamed]),
+            [
+                "diff --git a/src/lib.rs b/src/core.rs",
+                "rename from src/lib.rs",
+                "rename to src/core.rs",
+            ]
+        );
+    }
+
+    #[test]
+    fn stat_lists_status_paths_and_counts() {
+        let mut renamed = modified();
+        renamed.status = Delta::Renamed;
+        renamed.old_path = "src/a.rs".into();
+        let mut image = modified();
+        image.status = Delta::Added;
+        image.new_path = "logo.png".into();
+        image.binary = true;
+
+        assert_eq!(
+            stat(&[modified(), renamed, image]),
+            [
+                "M src/lib.rs             | +1 -1",
+                "R src/a.rs => src/lib.rs | +1 -1",
+                "A logo.png               | binary",
+                "3 files changed, 2 insertions(+), 2 deletions(-)",
+            ]
+        );
+        assert!(stat(&[]).is_empty());
+    }
+}
@@ -0,0 +1,162 @@
+/*!
+ * Copyright (c) 2026 mingcheng <mingcheng@apache.org>
+ *
+ * This source code is licensed under the MIT License,
+ * which is located in the LICENSE file in the source tree's root directory.
+ *
+ * Paths left out of the diff sent to the model.
+ *
+ * Patterns use gitignore syntax and are read, in increasing precedence, from
+ * the built-in defaults, the `exclude` config key and the repository's
+ * `.aigitcommitignore`, so a later `!pattern` re-includes a path excluded by
+ * an earlier one.
+ *
+ * File: exclude.rs
+ * Author: mingcheng <mingcheng@apache.org>
+ * File Created: 2026-10-17 11:04:51
+ *
+ * Modified By: mingcheng <mingcheng@apache.org>
+ * Last Modified: 2026-10-17 11:04:51
+ */
+
+use ignore::Match;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use serde::Serialize;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+/// Repository-level file with extra exclude patterns, at the work tree root.
+pub const IGNORE_FILE: &str = ".aigitcommitignore";
+
+/// Files commonly auto-generated or noisy that should be excluded from the
+/// diff sent to the model. Lock files are summarized instead, see
+/// [`crate::deps`].
+pub const DEFAULT_PATTERNS: &[&str] = &["*.min.js", "*.min.css", "*.js.map", "*.css.map"];
+
+/// Why a path was left out of the diff.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum Reason {
+    /// Matched an exclude pattern.
+    Pattern { pattern: String },
+    /// Marked `linguist-generated` in gitattributes.
+    Generated,
+    /// Marked `-diff` in gitattributes.
+    NoDiff,
+}
+
+/// A path left out of the diff.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Excluded {
+    pub path: String,
+    #[serde(flatten)]
+    pub reason: Reason,
+}
+
+impl Display for Excluded {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.reason {
+            Reason::Pattern { pattern } => write!(f, "{} (pattern `{pattern}`)", self.path),
+            Reason::Generated => write!(f, "{} (linguist-generated)", self.path),
+            Reason::NoDiff => write!(f, "{} (-diff)", self.path),
+        }
+    }
+}
+
+/// Compiled exclude patterns of a work tree.
+pub struct Exclude {
+    matcher: Gitignore,
+}
+
+impl Exclude {
+    /// Compile the default patterns, `patterns` and the `.aigitcommitignore`
+    /// file under `root`, if there is one.
+    pub fn new(root: &Path, patterns: &[String]) -> Result<Self, Box<dyn Error>> {
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in DEFAULT_PATTERNS {
+            builder.add_line(None, pattern)?;
+        }
+        for pattern in patterns {
+            builder
+                .add_line(None, pattern)
+                .map_err(|e| format!("invalid exclude pattern `{pattern}`: {e}"))?;
+        }
+        let file = root.join(IGNORE_FILE);
+        if file.is_file()
+            && let Some(e) = builder.add(&file)
+        {
+            return Err(format!("failed to read {}: {e}", file.display()).into());
+        }
+
+        Ok(Self {
+            matcher: builder.build()?,
+        })
+    }
+
+    /// The pattern excluding `path`, relative to the work tree root.
+    ///
+    /// A pattern matching a parent directory excludes everything below it.
+    pub fn matches(&self, path: &str) -> Option<&str> {
+        match self.matcher.matched_path_or_any_parents(path, false) {
+            Match::Ignore(glob) => Some(glob.original()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exclude(patterns: &[&str]) -> Exclude {
+        let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+        Exclude::new(Path::new("/nonexistent-aigitcommit-root"), &patterns).unwrap()
+    }
+
+    #[test]
+    fn matches_gitignore_patterns() {
+        let exclude = exclude(&["vendor/", "Cargo.lock", "docs/generated/**"]);
+        assert_eq!(exclude.matches("Cargo.lock"), Some("Cargo.lock"));
+        assert_eq!(exclude.matches("web/Cargo.lock"), Some("Cargo.lock"));
+        assert_eq!(exclude.matches("vendor/x/y.go"), Some("vendor/"));
+        assert_eq!(exclude.matches("static/app.min.js"), Some("*.min.js"));
+        assert_eq!(
+            exclude.matches("docs/generated/api.md"),
+            Some("docs/generated/**")
+        );
+        assert_eq!(exclude.matches("docs/guide.md"), None);
+        assert_eq!(exclude.matches("src/main.rs"), None);
+    }
+
+    #[test]
+    fn later_patterns_can_re_include() {
+        let exclude = exclude(&["!vendor/app.min.js"]);
+        assert_eq!(exclude.matches("vendor/app.min.js"), None);
+        assert_eq!(exclude.matches("vendor/lib.min.js"), Some("*.min.js"));
+    }
+
+    #[test]
+    fn reads_the_ignore_file() {
+        let root = std::env::temp_dir().join(format!("aigitcommit-exclude-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join(IGNORE_FILE), "# generated\n*.pb.go\n").unwrap();
+
+        let exclude = Exclude::new(&root, &[]).unwrap();
+        assert_eq!(exclude.matches("api/v1/service.pb.go"), Some("*.pb.go"));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn displays_the_reason() {
+        let excluded = Excluded {
+            path: "gen/x.rs".into(),
+            reason: Reason::Generated,
+        };
+        assert_eq!(excluded.to_string(), "gen/x.rs (linguist-generated)");
+        assert_eq!(
+            serde_json::to_value(&excluded).unwrap(),
+            serde_json::json!({"path": "gen/x.rs", "reason": "generated"})
+        );
+    }
+}
@@ -0,0 +1,170 @@
+/*!
+ * Copyright (c) 2026 mingcheng <mingcheng@apache.org>
+ *
+ * This source code is licensed under the MIT License,
+ * which is located in the LICENSE file in the source tree's root directory.
+ *
+ * Selection of past commits sent to the model as style reference.
+ *
+ * Commits touching the same paths as the described changes are preferred
+ * over merely recent ones, since a monorepo's newest commits often come from
+ * unrelated areas. Merge and revert commits are skipped because their
+ * generated messages say nothing about the project's style. The selection
+ * only depends on the commit graph, so the response cache key stays stable.
+ *
+ * File: history.rs
+ * Author: mingcheng <mingcheng@apache.org>
+ * File Created: 2026-10-17 17:12:08
+ *
+ * Modified By: mingcheng <mingcheng@apache.org>
+ * Last Modified: 2026-10-17 17:12:08
+ */
+
+use crate::git::diff::FileDiff;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Upper bound of commits inspected while looking for related ones.
+pub const MAX_SCANNED: usize = 200;
+
+/// History settings, the `[history]` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryConfig {
+    /// Prefer commits touching the changed files or their directories.
+    pub paths: bool,
+    /// Only use commits whose author name or email contains this text,
+    /// ignoring case.
+    pub author: Option<String>,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            paths: true,
+            author: None,
+        }
+    }
+}
+
+/// Which commits are selected as style reference.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct History {
+    /// Number of commit messages to select.
+    pub count: usize,
+    /// Lowercase text the author name or email must contain.
+    pub author: Option<String>,
+    /// Pathspecs of the described changes; commits touching them come
+    /// first. Empty to take the newest commits.
+    pub paths: Vec<String>,
+}
+
+impl History {
+    /// The `count` newest commits, without preferring any paths.
+    pub fn recent(count: usize) -> Self {
+        Self {
+            count,
+            ..Self::default()
+        }
+    }
+
+    /// The selection configured by `config` for the changes in `files`.
+    pub fn new(count: usize, config: &HistoryConfig, files: &[FileDiff]) -> Self {
+        Self {
+            count,
+            author: config
+                .author
+                .as_deref()
+                .map(str::trim)
+                .filter(|a| !a.is_empty())
+                .map(str::to_lowercase),
+            paths: if config.paths {
+                pathspecs(files)
+            } else {
+                Vec::new()
+            },
+        }
+    }
+
+    /// Whether `commit` may serve as style reference at all.
+    pub fn accepts(&self, commit: &git2::Commit) -> bool {
+        if commit.parent_count() > 1 {
+            return false;
+        }
+        let message = commit.message().unwrap_or_default().trim();
+        if message.is_empty() || is_revert(message) {
+            return false;
+        }
+
+        match &self.author {
+            Some(author) => {
+                let signature = commit.author();
+                [signature.name(), signature.email()]
+                    .into_iter()
+                    .flatten()
+                    .any(|s| s.to_lowercase().contains(author))
+            }
+            None => true,
+        }
+    }
+}
+
+/// Whether `message` is the one `git revert` writes.
+fn is_revert(message: &str) -> bool {
+    message.starts_with("Revert \"") || message.contains("This reverts commit ")
+}
+
+/// The changed paths and their parent directories, sorted.
+///
+/// Directories make new files relate to the history of their neighbours;
+/// the work tree root is left out as it would match every commit.
+fn pathspecs(files: &[FileDiff]) -> Vec<String> {
+    let mut paths = BTreeSet::new();
+    for file in files {
+        for path in [&file.old_path, &file.new_path] {
+            if path.is_empty() {
+                continue;
+            }
+            paths.insert(path.clone());
+            if let Some(parent) = Path::new(path).parent().and_then(Path::to_str)
+                && !parent.is_empty()
+            {
+                paths.insert(parent.to_string());
+            }
+        }
+    }
+    paths.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_reverts() {
+        assert!(is_revert(
+            "Revert \"feat: add x\"\n\nThis reverts commit abc."
+        ));
+        assert!(is_revert("undo x\n\nThis reverts commit 1234abcd."));
+        assert!(!is_revert("revert: drop the retry loop"));
+        assert!(!is_revert("fix: revert to the old parser"));
+    }
+
+    #[test]
+    fn normalizes_the_author() {
+        let config = HistoryConfig {
+            paths: false,
+            author: Some(" Alice@Example.com ".into()),
+        };
+        let history = History::new(3, &config, &[]);
+        assert_eq!(history.author.as_deref(), Some("alice@example.com"));
+        assert!(history.paths.is_empty());
+
+        let config = HistoryConfig {
+            author: Some(String::new()),
+            ..HistoryConfig::default()
+        };
+        assert_eq!(History::new(3, &config, &[]), History::recent(3));
+    }
+}
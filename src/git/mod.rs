@@ -12,5 +12,8 @@
  * Last Modified: 2026-05-07 11:30:45
  */
 
+pub mod diff;
+pub mod exclude;
+pub mod history;
 pub mod message;
 pub mod repository;
@@ -12,28 +12,24 @@
  * Last Modified: 2026-05-07 11:30:38
  */
 
-use git2::{Oid, Repository as _Repo, RepositoryOpenFlags, Signature};
+use git2::{
+    AttrCheckFlags, AttrValue, Oid, Patch, Repository as _Repo, RepositoryOpenFlags, Signature,
+};
 use regex::Regex;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::fs;
 use std::path::Path;
 use std::sync::LazyLock;
-use tracing::{trace, warn};
+use tracing::{debug, trace, warn};
 
+use crate::deps;
+use crate::git::diff::{self, Changes, DiffSpec, FileDiff, Scope, Summary, SummaryKind};
+use crate::git::exclude::{Exclude, Excluded, Reason};
+use crate::git::history::{History, MAX_SCANNED};
 use crate::git::message::GitMessage;
 use crate::utils::env;
 
-/// Files commonly auto-generated or noisy that should be excluded from the
-/// diff sent to the model.
-const EXCLUDED_FILES: &[&str] = &[
-    "go.mod",
-    "go.sum",
-    "Cargo.lock",
-    "package-lock.json",
-    "yarn.lock",
-    "pnpm-lock.yaml",
-];
-
 /// Compiled once: validates a minimally well-formed `local@domain.tld` email.
 static EMAIL_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").expect("valid email regex"));
@@ -95,6 +91,15 @@ pub fn git_dir(&self) -> &Path {
         self.repository.path()
     }
 
+    /// Get the path to the working directory root.
+    ///
+    /// Always present, since bare repositories are rejected by [`Repository::new`].
+    pub fn work_dir(&self) -> &Path {
+        self.repository
+            .workdir()
+            .expect("bare repositories are rejected on open")
+    }
+
     /// Commit the staged changes in the repository
     ///
     /// # Arguments
@@ -146,6 +151,63 @@ pub fn commit(&self, message: &GitMessage) -> Result<Oid, Box<dyn Error>> {
         Ok(result)
     }
 
+    /// Replace HEAD with a commit of the staged changes and `message`
+    ///
+    /// Like `git commit --amend`, the author and author date of HEAD are
+    /// kept, the committer becomes the current user and HEAD's parents stay
+    /// the parents of the new commit.
+    ///
+    /// # Arguments
+    /// * `message` - The commit message to use
+    ///
+    /// # Returns
+    /// * `Ok(Oid)` - Id of the rewritten commit
+    /// * `Err` - No commit to amend, or failed to write it
+    pub fn amend(&self, message: &GitMessage) -> Result<Oid, Box<dyn Error>> {
+        let head = self.amended_commit()?;
+        let tree = self
+            .repository
+            .find_tree(self.repository.index()?.write_tree()?)?;
+
+        let author = self.get_author()?;
+        let committer = Signature::now(&author.name, &author.email)?;
+        let oid = head.amend(
+            Some("HEAD"),
+            None,
+            Some(&committer),
+            None,
+            Some(&message.to_string()),
+            Some(&tree),
+        )?;
+
+        trace!("amended {} into {oid}", head.id());
+        Ok(oid)
+    }
+
+    /// Stage the changes of tracked files matching `pathspecs`, or of all
+    /// tracked files when empty, like `git commit -a` does.
+    ///
+    /// # Arguments
+    /// * `pathspecs` - Paths to stage
+    /// * `untracked` - Also add untracked files that are not ignored
+    pub fn stage(&self, pathspecs: &[String], untracked: bool) -> Result<(), Box<dyn Error>> {
+        let mut index = self.repository.index()?;
+        let all = ["*".to_string()];
+        let pathspecs = if pathspecs.is_empty() {
+            &all[..]
+        } else {
+            pathspecs
+        };
+
+        index.update_all(pathspecs, None)?;
+        if untracked {
+            index.add_all(pathspecs, git2::IndexAddOption::DEFAULT, None)?;
+        }
+        index.write()?;
+        trace!("staged changes of {pathspecs:?}");
+        Ok(())
+    }
+
     /// Get the author email and name from the repository configuration
     ///
     /// Attempts to read user.name and user.email from git config.
@@ -204,26 +266,31 @@ pub fn get_author(&self) -> Result<Author, Box<dyn Error>> {
         Ok(Author { name, email })
     }
 
-    /// Get the diff of staged changes (index vs HEAD).
+    /// Get the diff selected by `spec` as unified-diff lines.
     ///
-    /// Lock files and other generated noise listed in [`EXCLUDED_FILES`] are
-    /// stripped so they don't dominate the prompt.
+    /// Minified assets and other noise matching the patterns of [`Exclude`]
+    /// are stripped so they don't dominate the prompt, as are files marked
+    /// `linguist-generated` or `-diff` in gitattributes.
+    ///
+    /// # Arguments
+    /// * `spec` - Changes to diff, pathspecs, extra exclude patterns and the
+    ///   rename detection threshold
     ///
     /// # Returns
     /// * `Ok(Vec<String>)` - Lines of the diff in patch format
     /// * `Err` - Failed to generate diff
-    pub fn get_diff(&self) -> Result<Vec<String>, Box<dyn Error>> {
-        let index = self.repository.index()?;
+    pub fn get_diff(&self, spec: &DiffSpec) -> Result<Vec<String>, Box<dyn Error>> {
+        Ok(diff::render(&self.get_changes(spec)?.files))
+    }
 
-        // Get the HEAD tree, or None for initial commit
-        let head_tree = match self.repository.head() {
-            Ok(head_ref) => Some(head_ref.peel_to_commit()?.tree()?),
-            Err(e) if e.code() == git2::ErrorCode::UnbornBranch => {
-                trace!("generating diff for initial commit");
-                None
-            }
-            Err(e) => return Err(Box::new(e)),
-        };
+    /// Get the diff selected by `spec` as one [`FileDiff`] per file, along
+    /// with the paths left out.
+    ///
+    /// Applies the same exclusions and rename detection as
+    /// [`Repository::get_diff`].
+    pub fn get_changes(&self, spec: &DiffSpec) -> Result<Changes, Box<dyn Error>> {
+        let exclude = Exclude::new(self.work_dir(), &spec.excludes)?;
+        let index = self.repository.index()?;
 
         // Configure diff options
         let mut diffopts = git2::DiffOptions::new();
@@ -233,107 +300,336 @@ pub fn get_diff(&self) -> Result<Vec<String>, Box<dyn Error>> {
             .ignore_submodules(true)
             .minimal(true)
             .context_lines(3);
+        for pathspec in &spec.pathspecs {
+            diffopts.pathspec(pathspec);
+        }
+        if spec.scope.untracked() {
+            diffopts
+                .include_untracked(true)
+                .recurse_untracked_dirs(true)
+                .show_untracked_content(true);
+        }
 
-        // Generate diff between HEAD and index (staged changes)
-        let diff = self.repository.diff_tree_to_index(
-            head_tree.as_ref(),
-            Some(&index),
-            Some(&mut diffopts),
-        )?;
+        let mut diff = match &spec.scope {
+            // HEAD vs index, the changes `git commit` records
+            Scope::Staged => self.repository.diff_tree_to_index(
+                self.head_tree()?.as_ref(),
+                Some(&index),
+                Some(&mut diffopts),
+            )?,
+            Scope::Unstaged { .. } => self
+                .repository
+                .diff_index_to_workdir(Some(&index), Some(&mut diffopts))?,
+            Scope::All { .. } => self
+                .repository
+                .diff_tree_to_workdir_with_index(self.head_tree()?.as_ref(), Some(&mut diffopts))?,
+            Scope::Amend => {
+                let parent = self.amended_commit()?.parents().next();
+                self.repository.diff_tree_to_index(
+                    parent.map(|p| p.tree()).transpose()?.as_ref(),
+                    Some(&index),
+                    Some(&mut diffopts),
+                )?
+            }
+            Scope::Revision(rev) => {
+                let commit = self.find_commit(rev)?;
+                let parent = commit.parents().next().map(|p| p.tree()).transpose()?;
+                self.repository.diff_tree_to_tree(
+                    parent.as_ref(),
+                    Some(&commit.tree()?),
+                    Some(&mut diffopts),
+                )?
+            }
+            Scope::Range(range) => {
+                let (from, to) = self.resolve_range(range)?;
+                self.repository.diff_tree_to_tree(
+                    Some(&from.tree()?),
+                    Some(&to.tree()?),
+                    Some(&mut diffopts),
+                )?
+            }
+        };
 
-        let mut result = Vec::new();
+        // Pair deleted and added files into renames, and detect copies of
+        // modified files, so a move is not shown as a full delete and add.
+        if spec.rename_threshold > 0 {
+            let mut findopts = git2::DiffFindOptions::new();
+            findopts
+                .renames(true)
+                .copies(true)
+                .for_untracked(spec.scope.untracked())
+                .rename_threshold(spec.rename_threshold)
+                .copy_threshold(spec.rename_threshold);
+            diff.find_similar(Some(&mut findopts))?;
+        }
 
-        diff.print(git2::DiffFormat::Patch, |delta, _hunk, line| {
-            // Skip excluded files entirely.
-            if let Some(name) = delta
-                .new_file()
+        let mut changes = Changes::default();
+        for (idx, delta) in diff.deltas().enumerate() {
+            let file = if delta.status() == git2::Delta::Deleted {
+                delta.old_file()
+            } else {
+                delta.new_file()
+            };
+            let path = file
                 .path()
-                .and_then(|p| p.file_name())
-                .map(|f| f.to_string_lossy().into_owned())
-                && EXCLUDED_FILES.contains(&name.as_str())
-            {
-                warn!("skipping excluded file: {name}");
-                return true;
+                .map(|p| p.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            // Skip excluded files entirely.
+            let reason = match exclude.matches(&path) {
+                Some(pattern) => Some(Reason::Pattern {
+                    pattern: pattern.to_string(),
+                }),
+                None => self.attr_reason(&path),
+            };
+            if let Some(reason) = reason {
+                let excluded = Excluded { path, reason };
+                debug!("skipping excluded file: {excluded}");
+                changes.excluded.push(excluded);
+                continue;
             }
 
-            // Add non-empty lines to result
-            let content = String::from_utf8_lossy(line.content());
-            let trimmed = content.trim();
-            if !trimmed.is_empty() {
-                result.push(trimmed.to_string());
+            if let Some(patch) = Patch::from_diff(&diff, idx)? {
+                let mut file = FileDiff::from_patch(&patch)?;
+                if deps::is_supported(&path) && !file.binary {
+                    let old = self.blob_text(file.old_oid)?;
+                    // Working tree content is not in the object database.
+                    let new = if spec.scope.workdir() {
+                        fs::read(self.work_dir().join(&path))
+                            .map(|b| String::from_utf8_lossy(&b).into_owned())
+                            .unwrap_or_default()
+                    } else {
+                        self.blob_text(file.new_oid)?
+                    };
+                    match deps::summarize(&path, &old, &new) {
+                        Some(lines) => {
+                            file.summary = Some(Summary {
+                                kind: SummaryKind::Dependencies,
+                                lines,
+                            })
+                        }
+                        None => debug!("cannot parse the dependencies of {path}, sending the diff"),
+                    }
+                }
+                changes.files.push(file);
             }
-            true
-        })?;
+        }
 
-        Ok(result)
+        Ok(changes)
     }
 
-    /// Check if commit should be signed off
-    /// Returns true when git config `aigitcommit.signoff` is enabled or the
-    /// `AIGITCOMMIT_SIGNOFF` environment variable evaluates to true.
-    ///
-    /// The env variable is consulted whenever the config key is missing or
-    /// not set to true, so users can opt in globally without touching git
-    /// config in every repository.
-    pub fn should_signoff(&self) -> bool {
-        // Define the config key for signoff
-        const SIGNOFF_KEY: &str = "aigitcommit.signoff";
-
-        let from_config = self
+    /// The HEAD commit, or `None` before the first commit.
+    fn head_commit(&self) -> Result<Option<git2::Commit<'_>>, Box<dyn Error>> {
+        match self.repository.head() {
+            Ok(head_ref) => Ok(Some(head_ref.peel_to_commit()?)),
+            Err(e) if e.code() == git2::ErrorCode::UnbornBranch => {
+                trace!("HEAD is unborn, there are no commits yet");
+                Ok(None)
+            }
+            Err(e) => Err(Box::new(e)),
+        }
+    }
+
+    /// The tree of HEAD, or `None` before the first commit.
+    fn head_tree(&self) -> Result<Option<git2::Tree<'_>>, Box<dyn Error>> {
+        Ok(self.head_commit()?.map(|c| c.tree()).transpose()?)
+    }
+
+    /// The HEAD commit, which `--amend` rewrites.
+    fn amended_commit(&self) -> Result<git2::Commit<'_>, Box<dyn Error>> {
+        self.head_commit()?
+            .ok_or_else(|| "there is no commit to amend yet".into())
+    }
+
+    /// The commit a revision such as `HEAD~2` or a branch name points to.
+    fn find_commit(&self, rev: &str) -> Result<git2::Commit<'_>, Box<dyn Error>> {
+        let object = self
             .repository
-            .config()
-            .ok()
-            .and_then(|c| c.get_bool(SIGNOFF_KEY).ok())
-            .unwrap_or(false);
-        trace!("✍️ git config signoff: {}", from_config);
+            .revparse_single(rev)
+            .map_err(|e| format!("unknown revision `{rev}`: {}", e.message()))?;
+        Ok(object.peel_to_commit()?)
+    }
 
-        from_config || env::get_bool("AIGITCOMMIT_SIGNOFF")
+    /// The two ends of `A..B`; for `A...B` the first end is their merge base.
+    fn resolve_range(
+        &self,
+        range: &str,
+    ) -> Result<(git2::Commit<'_>, git2::Commit<'_>), Box<dyn Error>> {
+        let spec = self
+            .repository
+            .revparse(range)
+            .map_err(|e| format!("invalid range `{range}`: {}", e.message()))?;
+        if !spec.mode().contains(git2::RevparseMode::RANGE) {
+            return Err(format!("`{range}` is not a range, expected `A..B`").into());
+        }
+        let (Some(from), Some(to)) = (spec.from(), spec.to()) else {
+            return Err(format!("`{range}` is not a range, expected `A..B`").into());
+        };
+        let (from, to) = (from.peel_to_commit()?, to.peel_to_commit()?);
+        if spec.mode().contains(git2::RevparseMode::MERGE_BASE) {
+            let base = self.repository.merge_base(from.id(), to.id())?;
+            return Ok((self.repository.find_commit(base)?, to));
+        }
+        Ok((from, to))
     }
 
-    /// Get the latest `size` commit messages from the repository
+    /// Content of the blob `oid`, empty for the zero id of a missing side.
+    fn blob_text(&self, oid: Oid) -> Result<String, Box<dyn Error>> {
+        if oid.is_zero() {
+            return Ok(String::new());
+        }
+        let blob = self.repository.find_blob(oid)?;
+        Ok(String::from_utf8_lossy(blob.content()).into_owned())
+    }
+
+    /// Gitattributes that keep `path` out of the diff, if any.
+    fn attr_reason(&self, path: &str) -> Option<Reason> {
+        let attr = |name| {
+            let value = self
+                .repository
+                .get_attr(Path::new(path), name, AttrCheckFlags::INDEX_THEN_FILE)
+                .ok()
+                .flatten();
+            AttrValue::from_string(value)
+        };
+
+        match attr("linguist-generated") {
+            AttrValue::True | AttrValue::String("true") => return Some(Reason::Generated),
+            _ => {}
+        }
+        if attr("diff") == AttrValue::False {
+            return Some(Reason::NoDiff);
+        }
+        None
+    }
+
+    /// Get the commit messages selected by `history` preceding the changes of `scope`
     ///
-    /// Retrieves commit messages in reverse chronological order (newest first).
+    /// Walks the history newest first, starting at HEAD for uncommitted
+    /// changes, at the first parent of a described revision and at the start
+    /// of a described range, so the commits being described are never part
+    /// of their own history. Before the first commit, and for a root commit,
+    /// the history is empty.
+    ///
+    /// Merge and revert commits and those of other authors are skipped.
+    /// Commits touching `history.paths` are taken first, the newest of the
+    /// others fill up the remaining slots. The messages keep the walk order,
+    /// which is topological so commits with equal timestamps stay stable.
     ///
     /// # Arguments
-    /// * `size` - Maximum number of commit messages to retrieve
+    /// * `history` - Number of messages and how to select them
+    /// * `scope` - The changes the history is collected for
     ///
     /// # Returns
-    /// * `Ok(Vec<String>)` - List of commit messages (may be fewer than `size` if repo has fewer commits)
+    /// * `Ok(Vec<String>)` - List of commit messages (may be fewer than requested if repo has fewer commits)
     /// * `Err` - Failed to walk commit history
-    pub fn get_logs(&self, size: usize) -> Result<Vec<String>, Box<dyn Error>> {
+    pub fn get_logs(
+        &self,
+        history: &History,
+        scope: &Scope,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        let start = match scope {
+            Scope::Staged | Scope::Unstaged { .. } | Scope::All { .. } => {
+                match self.head_commit()? {
+                    Some(head) => head.id(),
+                    None => return Ok(Vec::new()),
+                }
+            }
+            Scope::Amend => match self.amended_commit()?.parent_ids().next() {
+                Some(parent) => parent,
+                None => return Ok(Vec::new()),
+            },
+            Scope::Revision(rev) => match self.find_commit(rev)?.parent_ids().next() {
+                Some(parent) => parent,
+                None => return Ok(Vec::new()),
+            },
+            Scope::Range(range) => self.resolve_range(range)?.0.id(),
+        };
+
+        if history.count == 0 {
+            return Ok(Vec::new());
+        }
+
         let mut revwalk = self.repository.revwalk()?;
+        revwalk.push(start)?;
 
-        // Start walking from HEAD
-        revwalk.push_head()?;
-
-        // Sort by time (newest first) - this is the default but made explicit
-        revwalk.set_sorting(git2::Sort::TIME)?;
-
-        // Collect up to `size` commit messages
-        let commits: Vec<String> = revwalk
-            .take(size)
-            .filter_map(|oid_result| {
-                oid_result
-                    .ok()
-                    .and_then(|oid| self.repository.find_commit(oid).ok())
-                    .and_then(|commit| {
-                        commit
-                            .message()
-                            .map(str::trim)
-                            .filter(|msg| !msg.is_empty())
-                            .map(String::from)
-                    })
-            })
-            .collect();
+        // Newest first, parents always after their children
+        revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+
+        // Candidates as (walk position, message)
+        let mut related = Vec::new();
+        let mut recent = Vec::new();
+        for (position, oid) in revwalk.take(MAX_SCANNED).enumerate() {
+            let commit = self.repository.find_commit(oid?)?;
+            if !history.accepts(&commit) {
+                continue;
+            }
+
+            let message = commit.message().unwrap_or_default().trim().to_string();
+            if !history.paths.is_empty() && self.touches(&commit, &history.paths)? {
+                related.push((position, message));
+            } else if recent.len() < history.count {
+                recent.push((position, message));
+            }
+
+            let found = if history.paths.is_empty() {
+                recent.len()
+            } else {
+                related.len()
+            };
+            if found >= history.count {
+                break;
+            }
+        }
+
+        recent.truncate(history.count.saturating_sub(related.len()));
+        let mut commits = related;
+        commits.append(&mut recent);
+        commits.sort_unstable_by_key(|(position, _)| *position);
 
         trace!("retrieved {} commit messages", commits.len());
-        Ok(commits)
+        Ok(commits.into_iter().map(|(_, message)| message).collect())
+    }
+
+    /// Whether the changes of `scope` make up the first commit of the
+    /// repository, as opposed to a commit whose history is merely empty
+    /// after filtering.
+    ///
+    /// # Returns
+    /// * `Ok(true)` - HEAD is unborn, or the described or amended commit has no parent
+    /// * `Err` - The revision or range cannot be resolved
+    pub fn describes_root_commit(&self, scope: &Scope) -> Result<bool, Box<dyn Error>> {
+        Ok(match scope {
+            Scope::Staged | Scope::Unstaged { .. } | Scope::All { .. } => {
+                self.head_commit()?.is_none()
+            }
+            Scope::Amend => self.amended_commit()?.parent_count() == 0,
+            Scope::Revision(rev) => self.find_commit(rev)?.parent_count() == 0,
+            Scope::Range(_) => false,
+        })
+    }
+
+    /// Whether `commit` changed any of `pathspecs` compared to its first parent.
+    fn touches(&self, commit: &git2::Commit, pathspecs: &[String]) -> Result<bool, Box<dyn Error>> {
+        let parent = commit.parents().next().map(|p| p.tree()).transpose()?;
+        let mut diffopts = git2::DiffOptions::new();
+        for pathspec in pathspecs {
+            diffopts.pathspec(pathspec);
+        }
+
+        let diff = self.repository.diff_tree_to_tree(
+            parent.as_ref(),
+            Some(&commit.tree()?),
+            Some(&mut diffopts),
+        )?;
+        Ok(diff.deltas().len() > 0)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::git::history::HistoryConfig;
     use tracing::error;
 
     fn setup() -> Result<Repository, Box<dyn Error>> {
@@ -367,13 +663,497 @@ fn test_logs() {
             return;
         }
 
-        let logs = repo.unwrap().get_logs(5);
+        let logs = repo.unwrap().get_logs(&History::recent(5), &Scope::Staged);
         assert!(logs.is_ok());
         // May have fewer than 5 commits if repo is new
         let log_list = logs.unwrap();
         assert!(log_list.len() <= 5);
     }
 
+    /// A repository in a fresh temp dir with `files` committed on HEAD.
+    fn temp_repo(name: &str, files: &[(&str, &str)]) -> (std::path::PathBuf, _Repo) {
+        let dir = std::env::temp_dir().join(format!("aigitcommit-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let repo = _Repo::init(&dir).unwrap();
+        commit_files(&repo, files, "init");
+        (dir, repo)
+    }
+
+    /// Stage `files` and commit them on top of HEAD, if any.
+    fn commit_files(repo: &_Repo, files: &[(&str, &str)], message: &str) -> Oid {
+        commit_as(repo, "Test", files, message)
+    }
+
+    /// Like [`commit_files`], authored by `name`.
+    fn commit_as(repo: &_Repo, name: &str, files: &[(&str, &str)], message: &str) -> Oid {
+        stage(repo, files);
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let email = format!("{}@example.com", name.to_lowercase());
+        let sig = Signature::now(name, &email).unwrap();
+        let parent = repo.head().ok().map(|h| h.peel_to_commit().unwrap());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+            .unwrap()
+    }
+
+    fn stage(repo: &_Repo, files: &[(&str, &str)]) {
+        let mut index = repo.index().unwrap();
+        for (path, content) in files {
+            let file = repo.workdir().unwrap().join(path);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, content).unwrap();
+            index.add_path(Path::new(path)).unwrap();
+        }
+        index.write().unwrap();
+    }
+
+    #[test]
+    fn test_diff_is_unified() {
+        let (dir, repo) = temp_repo(
+            "diff-unified",
+            &[("main.rs", "fn main() {\n    let a = 1;\n\n    run(a);\n}\n")],
+        );
+        stage(
+            &repo,
+            &[("main.rs", "fn main() {\n    let a = 2;\n\n    run(a);\n}")],
+        );
+
+        let repository = Repository::new(dir.to_str().unwrap()).unwrap();
+        let diff = repository.get_diff(&DiffSpec::default()).unwrap();
+        assert_eq!(diff[0], "diff --git a/main.rs b/main.rs");
+        assert!(diff[1].starts_with("index ") && diff[1].ends_with(" 100644"));
+        assert_eq!(
+            diff[2..],
+            [
+                "--- a/main.rs",
+                "+++ b/main.rs",
+                "@@ -1,5 +1,5 @@",
+                " fn main() {",
+                "-    let a = 1;",
+                "+    let a = 2;",
+                " ",
+                "     run(a);",
+                "-}",
+                "+}",
+                "\\ No newline at end of file",
+            ]
+        );
+
+        let files = repository.get_changes(&DiffSpec::default()).unwrap().files;
+        assert_eq!(files.len(), 1);
+        assert_eq!((files[0].additions(), files[0].deletions()), (2, 2));
+        assert_eq!(files[0].new_oid.to_string().len(), 40);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_diff_detects_renames() {
+        let body: String = (0..20).map(|i| format!("line {i}\n")).collect();
+        let (dir, repo) = temp_repo("diff-rename", &[("old.txt", &body)]);
+        std::fs::remove_file(dir.join("old.txt")).unwrap();
+        let mut index = repo.index().unwrap();
+        index.remove_path(Path::new("old.txt")).unwrap();
+        index.write().unwrap();
+        stage(&repo, &[("new.txt", &body)]);
+
+        let repository = Repository::new(dir.to_str().unwrap()).unwrap();
+        let files = repository.get_changes(&DiffSpec::default()).unwrap().files;
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].display_path(), "old.txt => new.txt");
+        assert_eq!(
+            repository.get_diff(&DiffSpec::default()).unwrap(),
+            [
+                "diff --git a/old.txt b/new.txt",
+                "rename from old.txt",
+                "rename to new.txt",
+            ]
+        );
+
+        // Without detection the move is a deletion plus an addition.
+        assert_eq!(
+            repository
+                .get_changes(&DiffSpec {
+                    rename_threshold: 0,
+                    ..DiffSpec::default()
+                })
+                .unwrap()
+                .files
+                .len(),
+            2
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_diff_scopes() {
+        let (dir, repo) = temp_repo("diff-scopes", &[("a.txt", "a\n"), ("b.txt", "b\n")]);
+        stage(&repo, &[("a.txt", "a staged\n")]);
+        std::fs::write(dir.join("b.txt"), "b edited\n").unwrap();
+        std::fs::write(dir.join("c.txt"), "c new\n").unwrap();
+
+        let repository = Repository::new(dir.to_str().unwrap()).unwrap();
+        let paths = |scope, pathspecs: &[&str]| -> Vec<String> {
+            let spec = DiffSpec {
+                scope,
+                pathspecs: pathspecs.iter().map(|p| p.to_string()).collect(),
+                ..DiffSpec::default()
+            };
+            let files = repository.get_changes(&spec).unwrap().files;
+            assert!(files.iter().all(|f| !f.new_oid.is_zero()));
+            files.iter().map(|f| f.path().to_string()).collect()
+        };
+
+        assert_eq!(paths(Scope::Staged, &[]), ["a.txt"]);
+        assert_eq!(paths(Scope::Unstaged { untracked: false }, &[]), ["b.txt"]);
+        assert_eq!(
+            paths(Scope::Unstaged { untracked: true }, &[]),
+            ["b.txt", "c.txt"]
+        );
+        assert_eq!(
+            paths(Scope::All { untracked: false }, &[]),
+            ["a.txt", "b.txt"]
+        );
+        assert_eq!(
+            paths(Scope::All { untracked: true }, &["b.txt", "c.txt"]),
+            ["b.txt", "c.txt"]
+        );
+
+        // `git commit -a`: tracked changes only.
+        repository.stage(&[], false).unwrap();
+        assert_eq!(paths(Scope::Staged, &[]), ["a.txt", "b.txt"]);
+        repository.stage(&["c.txt".to_string()], true).unwrap();
+        assert_eq!(paths(Scope::Staged, &[]), ["a.txt", "b.txt", "c.txt"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_diff_and_logs_of_revisions() {
+        let (dir, repo) = temp_repo("diff-revs", &[("a.txt", "a\n")]);
+        commit_files(&repo, &[("b.txt", "b\n")], "add b");
+        commit_files(&repo, &[("c.txt", "c\n")], "add c");
+        let repository = Repository::new(dir.to_str().unwrap()).unwrap();
+        let paths = |scope: Scope| -> Vec<String> {
+            let spec = DiffSpec {
+                scope,
+                ..DiffSpec::default()
+            };
+            let files = repository.get_changes(&spec).unwrap().files;
+            files.iter().map(|f| f.path().to_string()).collect()
+        };
+
+        let rev = Scope::Revision("HEAD~1".into());
+        assert_eq!(paths(rev.clone()), ["b.txt"]);
+        assert_eq!(
+            repository.get_logs(&History::recent(5), &rev).unwrap(),
+            ["init"]
+        );
+        let root = Scope::Revision("HEAD~2".into());
+        assert_eq!(paths(root.clone()), ["a.txt"]);
+        assert!(repository.describes_root_commit(&root).unwrap());
+        assert!(!repository.describes_root_commit(&rev).unwrap());
+        assert!(!repository.describes_root_commit(&Scope::Staged).unwrap());
+        let range = Scope::Range("HEAD~2..HEAD".into());
+        assert!(!repository.describes_root_commit(&range).unwrap());
+        assert!(
+            repository
+                .get_logs(&History::recent(5), &root)
+                .unwrap()
+                .is_empty()
+        );
+
+        let range = Scope::Range("HEAD~2..HEAD".into());
+        assert_eq!(paths(range.clone()), ["b.txt", "c.txt"]);
+        assert_eq!(
+            repository.get_logs(&History::recent(5), &range).unwrap(),
+            ["init"]
+        );
+        let logs = repository
+            .get_logs(&History::recent(5), &Scope::Staged)
+            .unwrap();
+        assert_eq!(logs, ["add c", "add b", "init"]);
+
+        let spec = DiffSpec {
+            scope: Scope::Range("HEAD".into()),
+            ..DiffSpec::default()
+        };
+        assert!(repository.get_changes(&spec).is_err());
+        let spec = DiffSpec {
+            scope: Scope::Revision("nope".into()),
+            ..DiffSpec::default()
+        };
+        let err = repository.get_changes(&spec).err().unwrap();
+        assert!(err.to_string().starts_with("unknown revision `nope`"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_logs_prefer_related_paths() {
+        let (dir, repo) = temp_repo("logs-related", &[("README.md", "# demo\n")]);
+        commit_as(
+            &repo,
+            "Alice",
+            &[("web/app.js", "a\n")],
+            "feat(web): add app",
+        );
+        commit_as(
+            &repo,
+            "Bob",
+            &[("api/main.go", "a\n")],
+            "feat(api): add server",
+        );
+        commit_as(
+            &repo,
+            "Alice",
+            &[("web/app.js", "b\n")],
+            "fix(web): escape input",
+        );
+        let reverted = commit_as(
+            &repo,
+            "Bob",
+            &[("web/app.js", "c\n")],
+            "Revert \"fix(web): escape input\"\n\nThis reverts commit 1.",
+        );
+        let head = repo.find_commit(reverted).unwrap();
+        let other = head.parent(0).unwrap();
+        let sig = Signature::now("Bob", "bob@example.com").unwrap();
+        repo.commit(
+            Some("HEAD"),
+            &sig,
+            &sig,
+            "Merge branch 'web'",
+            &head.tree().unwrap(),
+            &[&head, &other],
+        )
+        .unwrap();
+        commit_as(
+            &repo,
+            "Bob",
+            &[("api/db.go", "a\n")],
+            "feat(api): add database",
+        );
+        commit_as(
+            &repo,
+            "Bob",
+            &[("docs/api.md", "a\n")],
+            "docs: describe the api",
+        );
+        stage(&repo, &[("web/index.html", "<html>\n")]);
+
+        let repository = Repository::new(dir.to_str().unwrap()).unwrap();
+        let files = repository.get_changes(&DiffSpec::default()).unwrap().files;
+        let history = History::new(3, &HistoryConfig::default(), &files);
+        assert_eq!(history.paths, ["web", "web/index.html"]);
+        assert_eq!(
+            repository.get_logs(&history, &Scope::Staged).unwrap(),
+            [
+                "docs: describe the api",
+                "fix(web): escape input",
+                "feat(web): add app"
+            ]
+        );
+
+        assert_eq!(
+            repository
+                .get_logs(&History::recent(3), &Scope::Staged)
+                .unwrap(),
+            [
+                "docs: describe the api",
+                "feat(api): add database",
+                "fix(web): escape input"
+            ]
+        );
+
+        let config = HistoryConfig {
+            paths: false,
+            author: Some("BOB".into()),
+        };
+        assert_eq!(
+            repository
+                .get_logs(&History::new(5, &config, &files), &Scope::Staged)
+                .unwrap(),
+            [
+                "docs: describe the api",
+                "feat(api): add database",
+                "feat(api): add server"
+            ]
+        );
+        assert!(
+            repository
+                .get_logs(&History::recent(0), &Scope::Staged)
+                .unwrap()
+                .is_empty()
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_unborn_repository() {
+        let dir = std::env::temp_dir().join(format!("aigitcommit-unborn-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let repo = _Repo::init(&dir).unwrap();
+        stage(&repo, &[("README.md", "# demo\n")]);
+        std::fs::write(dir.join("main.rs"), "fn main() {}\n").unwrap();
+
+        let repository = Repository::new(dir.to_str().unwrap()).unwrap();
+        let files = repository.get_changes(&DiffSpec::default()).unwrap().files;
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].status_letter(), 'A');
+
+        let spec = DiffSpec {
+            scope: Scope::All { untracked: true },
+            ..DiffSpec::default()
+        };
+        let paths: Vec<String> = (repository.get_changes(&spec).unwrap().files)
+            .iter()
+            .map(|f| f.path().to_string())
+            .collect();
+        assert_eq!(paths, ["README.md", "main.rs"]);
+
+        for scope in [Scope::Staged, Scope::All { untracked: true }] {
+            assert!(repository.describes_root_commit(&scope).unwrap());
+            assert!(
+                repository
+                    .get_logs(&History::recent(5), &scope)
+                    .unwrap()
+                    .is_empty()
+            );
+        }
+        assert!(
+            repository
+                .amend(&GitMessage::new(&repository, "x", "y", false).unwrap())
+                .is_err()
+        );
+
+        let message =
+            GitMessage::new(&repository, "chore: initial commit", "- add readme", false).unwrap();
+        let oid = repository.commit(&message).unwrap();
+        assert_eq!(repo.find_commit(oid).unwrap().parent_count(), 0);
+        assert!(!repository.describes_root_commit(&Scope::Staged).unwrap());
+        assert!(repository.describes_root_commit(&Scope::Amend).unwrap());
+        assert_eq!(
+            repository
+                .get_logs(&History::recent(5), &Scope::Staged)
+                .unwrap()
+                .len(),
+            1
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_amend_keeps_author_and_parents() {
+        let (dir, repo) = temp_repo("amend", &[("a.txt", "a\n")]);
+        let sig = Signature::new(
+            "Original",
+            "original@example.com",
+            &git2::Time::new(1_000_000, 0),
+        )
+        .unwrap();
+        stage(&repo, &[("b.txt", "b\n")]);
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "wip", &tree, &[&head])
+            .unwrap();
+        stage(&repo, &[("c.txt", "c\n")]);
+
+        let repository = Repository::new(dir.to_str().unwrap()).unwrap();
+        let spec = DiffSpec {
+            scope: Scope::Amend,
+            ..DiffSpec::default()
+        };
+        let files = repository.get_changes(&spec).unwrap().files;
+        let paths: Vec<&str> = files.iter().map(|f| f.path()).collect();
+        assert_eq!(paths, ["b.txt", "c.txt"]);
+        assert_eq!(
+            repository
+                .get_logs(&History::recent(5), &Scope::Amend)
+                .unwrap(),
+            ["init"]
+        );
+
+        let message =
+            GitMessage::new(&repository, "feat: add b and c", "- add files", false).unwrap();
+        let oid = repository.amend(&message).unwrap();
+        let amended = repo.find_commit(oid).unwrap();
+        assert_eq!(repo.head().unwrap().target(), Some(oid));
+        assert_eq!(amended.message(), Some(message.to_string().as_str()));
+        assert_eq!(amended.author().name(), Some("Original"));
+        assert_eq!(amended.author().when().seconds(), 1_000_000);
+        assert_eq!(amended.parent_ids().collect::<Vec<_>>(), [head.id()]);
+        assert!(amended.tree().unwrap().get_name("c.txt").is_some());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_diff_summarizes_lock_files() {
+        let lock = |version: &str| {
+            format!("version = 4\n\n[[package]]\nname = \"serde\"\nversion = \"{version}\"\n")
+        };
+        let (dir, repo) = temp_repo("diff-lock", &[("Cargo.lock", &lock("1.0.200"))]);
+        stage(&repo, &[("Cargo.lock", &lock("1.0.210"))]);
+
+        let repository = Repository::new(dir.to_str().unwrap()).unwrap();
+        let diff = repository.get_diff(&DiffSpec::default()).unwrap();
+        assert_eq!(
+            diff[2..],
+            [
+                "[dependency changes, 6 diff lines not shown]",
+                "serde 1.0.200 → 1.0.210",
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_diff_excludes_patterns_and_attributes() {
+        let (dir, repo) = temp_repo(
+            "diff-exclude",
+            &[(
+                ".gitattributes",
+                "*.gen.rs linguist-generated\n*.bin -diff\n",
+            )],
+        );
+        std::fs::create_dir_all(dir.join("vendor")).unwrap();
+        std::fs::write(dir.join(".aigitcommitignore"), "vendor/\n").unwrap();
+        stage(
+            &repo,
+            &[
+                ("main.rs", "fn main() {}\n"),
+                ("vendor/dep.rs", "pub fn dep() {}\n"),
+                ("schema.gen.rs", "pub struct Schema;\n"),
+                ("blob.bin", "data\n"),
+                ("app.min.js", "a()\n"),
+                ("notes.txt", "todo\n"),
+            ],
+        );
+
+        let repository = Repository::new(dir.to_str().unwrap()).unwrap();
+        let changes = repository
+            .get_changes(&DiffSpec {
+                excludes: vec!["*.txt".to_string()],
+                ..DiffSpec::default()
+            })
+            .unwrap();
+        let paths: Vec<&str> = changes.files.iter().map(|f| f.path()).collect();
+        assert_eq!(paths, ["main.rs"]);
+        let excluded: Vec<String> = changes.excluded.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            excluded,
+            [
+                "app.min.js (pattern `*.min.js`)",
+                "blob.bin (-diff)",
+                "notes.txt (pattern `*.txt`)",
+                "schema.gen.rs (linguist-generated)",
+                "vendor/dep.rs (pattern `vendor/`)",
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     // #[test]
     // fn test_diff() {
     //     let repo = setup();
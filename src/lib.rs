@@ -16,8 +16,14 @@ pub mod built_info {
     include!(concat!(env!("OUT_DIR"), "/built.rs"));
 }
 
+pub mod budget;
 pub mod cache;
 pub mod cli;
+pub mod config;
+pub mod credential;
+pub mod deps;
 pub mod git;
-pub mod openai;
+pub mod prompt;
+pub mod provider;
+pub mod summarize;
 pub mod utils;
@@ -11,30 +11,29 @@
  * Last Modified: 2026-05-07 11:39:56
  */
 
+use aigitcommit::budget::Budget;
 use aigitcommit::built_info::{PKG_NAME, PKG_VERSION};
 use aigitcommit::cache::Cache;
-use aigitcommit::cli::{Cli, Command};
+use aigitcommit::cli::{Cli, Command, ConfigAction};
+use aigitcommit::config::{self, Config, Layers, REPO_CONFIG_FILE};
+use aigitcommit::git::diff::{self, DiffSpec, Scope};
+use aigitcommit::git::history::History;
 use aigitcommit::git::message::GitMessage;
 use aigitcommit::git::repository::Repository;
-use aigitcommit::openai::OpenAI;
+use aigitcommit::prompt;
+use aigitcommit::provider::fallback::Chain;
+use aigitcommit::provider::{self, Origin, Provider, Sink};
+use aigitcommit::summarize::Summarizer;
 use arboard::Clipboard;
-use async_openai::types::chat::{
-    ChatCompletionRequestSystemMessageArgs, ChatCompletionRequestUserMessageArgs,
-};
 use clap::Parser;
 use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use tracing::{Level, debug, error, info, trace};
 
-use aigitcommit::utils::{
-    self, OutputFormat, check_env_variables, env, install_hook, save_to_file, should_signoff,
-};
+use aigitcommit::utils::{self, LiveOutput, install_hook, print_explain, save_to_file};
 
-// Defaults and embedded resources.
-const DEFAULT_MODEL: &str = "gpt-5";
-const DEFAULT_LOG_COUNT: usize = 5;
-const SYSTEM_PROMPT: &str = include_str!("../templates/system.txt");
+// Embedded resources.
 const HOOK_NAME: &str = "prepare-commit-msg";
 const HOOK_CONTENT: &str = include_str!("../hooks/prepare-commit-msg");
 
@@ -50,30 +49,44 @@ async fn main() -> utils::Result<()> {
         println!("git hook `{HOOK_NAME}` has been installed successfully.");
         return Ok(());
     }
+    if let Some(Command::Config { action }) = &cli.command {
+        trace!("config subcommand invoked");
+        return run_config_command(action, &cli);
+    }
+
+    let repo_dir = resolve_repo_dir(&cli.repo_path)?;
+    trace!("specified repository directory: {repo_dir:?}");
+    let repository = Repository::new(
+        repo_dir
+            .to_str()
+            .ok_or("invalid UTF-8 in repository path")?,
+    );
 
-    let model_name = env::get("OPENAI_MODEL_NAME", DEFAULT_MODEL);
-    let client = OpenAI::new();
+    // The repository-local config file is only consulted when the path is
+    // inside a working tree; diagnostics still work outside of one.
+    let layers = Layers::load(
+        repository.as_ref().ok().map(Repository::work_dir),
+        &cli.overrides(),
+    )?;
 
     // Diagnostic flags also short-circuit before touching the repository.
     if cli.check_env {
-        debug!("model name: `{model_name}`");
-        check_env_variables();
+        print_explain(&layers);
         return Ok(());
     }
+
+    let config = layers.config()?;
+
     if cli.check_model {
+        let model_name = config.model.as_str();
         debug!("model name: `{model_name}`");
-        check_model_availability(&client, &model_name).await?;
+        // Only built here; generation builds its providers in the chain.
+        let client = provider::build(&config)?;
+        check_model_availability(client.as_ref(), model_name).await?;
         return Ok(());
     }
 
-    let repo_dir = resolve_repo_dir(&cli.repo_path)?;
-    trace!("specified repository directory: {repo_dir:?}");
-    let repository = Repository::new(
-        repo_dir
-            .to_str()
-            .ok_or("invalid UTF-8 in repository path")?,
-    )?;
-
+    let repository = repository?;
     let cache = Cache::new(repository.git_dir());
     if cli.clear_cache {
         let n = cache
@@ -84,33 +97,76 @@ async fn main() -> utils::Result<()> {
         return Ok(());
     }
 
-    let diffs = repository.get_diff()?;
-    debug!("got diff size is {}", diffs.len());
-    if diffs.is_empty() {
+    if cli.commit && !cli.all && !cli.pathspecs.is_empty() {
+        return Err("pathspecs with --commit require --all".into());
+    }
+    if cli.amend && !cli.pathspecs.is_empty() {
+        return Err("pathspecs cannot be combined with --amend".into());
+    }
+    let spec = DiffSpec {
+        scope: cli.scope(),
+        pathspecs: cli.pathspecs.clone(),
+        excludes: config.exclude.clone(),
+        rename_threshold: config.rename_threshold,
+    };
+    let changes = repository.get_changes(&spec)?;
+    for excluded in &changes.excluded {
+        info!("excluded from the diff: {excluded}");
+    }
+    debug!("got {} changed files", changes.files.len());
+    if changes.files.is_empty() {
         return Err("no changes found in the repository".into());
     }
 
-    let logs = repository.get_logs(DEFAULT_LOG_COUNT)?;
-    debug!("got logs size is {}", logs.len());
-    if logs.is_empty() {
-        return Err("no commit history found in the repository".into());
+    let history = History::new(config.log_count, &config.history, &changes.files);
+    let context = prompt::Context {
+        logs: repository.get_logs(&history, &spec.scope)?,
+        initial: repository.describes_root_commit(&spec.scope)?,
+        stat: diff::stat(&changes.files),
+        scope: spec.scope.clone(),
+    };
+    debug!("got logs size is {}", context.logs.len());
+    if context.initial {
+        info!("no commit history found, describing the first commit");
     }
 
-    let raw = generate_message(&client, &cache, &model_name, &logs, &diffs, cli.no_cache).await?;
+    let system_prompt = prompt::system(&config.language)?;
+    let budget = Budget::new(&config);
+    let chain = Chain::new(&config)?;
+    let mut fitted = budget.fit(&system_prompt, &context, &changes.files)?;
+    if fitted.overflow && config.summarize.enabled {
+        info!("the diff does not fit the prompt budget, summarizing large files");
+        let condensed = Summarizer::new(&chain, &cache, &budget, &config, !cli.no_cache)
+            .condense(&changes.files)
+            .await?;
+        fitted = budget.fit(&system_prompt, &context, &condensed)?;
+    }
+    let user_prompt = prompt::user(&context, &fitted.diff, &fitted.omitted)?;
+    let (raw, origin) = generate_message(
+        &chain,
+        &cache,
+        &config,
+        &system_prompt,
+        &user_prompt,
+        cli.no_cache,
+    )
+    .await?;
     let (title, content) = raw
         .split_once("\n\n")
         .ok_or("Invalid response format: expected title and content separated by double newline")?;
 
-    let need_signoff = should_signoff(&repository, cli.signoff);
+    let need_signoff = config.signoff;
     let message = GitMessage::new(&repository, title, content, need_signoff)?;
 
-    OutputFormat::detect(cli.json, cli.no_table).write(&message)?;
+    config
+        .output
+        .write(&message, origin.as_ref(), &changes.excluded)?;
 
     if cli.copy_to_clipboard {
         copy_to_clipboard(&message)?;
     }
-    if cli.commit {
-        run_commit_flow(&repository, &message, cli.yes)?;
+    if cli.commit || cli.amend {
+        run_commit_flow(&repository, &message, &spec, cli.yes)?;
     }
     if !cli.save.is_empty() {
         match save_to_file(&cli.save, &message) {
@@ -125,6 +181,68 @@ async fn main() -> utils::Result<()> {
     Ok(())
 }
 
+/// Handle `aigitcommit config <action>`.
+///
+/// Reads use the same layers as message generation, so `get` and `explain`
+/// reflect exactly what a normal run would see.
+fn run_config_command(action: &ConfigAction, cli: &Cli) -> utils::Result<()> {
+    let repository = Repository::new(&cli.repo_path).ok();
+    let repo_dir = repository.as_ref().map(Repository::work_dir);
+
+    // Writes go to the user file unless `--local` targets the repository.
+    let target_file = |local: bool| -> utils::Result<PathBuf> {
+        if local {
+            let dir = repo_dir.ok_or("--local requires a git repository")?;
+            Ok(dir.join(REPO_CONFIG_FILE))
+        } else {
+            Ok(config::user_config_path().ok_or("cannot determine the user config directory")?)
+        }
+    };
+
+    match action {
+        ConfigAction::Get { key } => {
+            let layers = Layers::load(repo_dir, &cli.overrides())?;
+            let entry = layers
+                .get(key)
+                .ok_or_else(|| format!("`{key}` is not set"))?;
+            match &entry.value {
+                toml::Value::String(s) => println!("{s}"),
+                other => println!("{other}"),
+            }
+        }
+        ConfigAction::Set { key, value, local } => {
+            if *local && config::is_user_only(key) {
+                return Err(format!(
+                    "`{key}` cannot be set in the repository config file, \
+                     which is shared with everyone who clones it"
+                )
+                .into());
+            }
+            let path = target_file(*local)?;
+            config::set_in_file(&path, key, value)?;
+            println!("set `{key}` in {}", path.display());
+        }
+        ConfigAction::Unset { key, local } => {
+            let path = target_file(*local)?;
+            if config::unset_in_file(&path, key)? {
+                println!("removed `{key}` from {}", path.display());
+            } else {
+                println!("`{key}` is not set in {}", path.display());
+            }
+        }
+        ConfigAction::List => {
+            let layers = Layers::load(repo_dir, &cli.overrides())?;
+            for (key, entry) in layers.iter() {
+                println!("{key} = {}", utils::display_value(key, &entry.value));
+            }
+        }
+        ConfigAction::Explain => {
+            print_explain(&Layers::load(repo_dir, &cli.overrides())?);
+        }
+    }
+    Ok(())
+}
+
 /// Canonicalize the user-supplied repository path and verify it is a directory.
 fn resolve_repo_dir(input: &str) -> utils::Result<PathBuf> {
     let dir = fs::canonicalize(Path::new(input))
@@ -135,31 +253,57 @@ fn resolve_repo_dir(input: &str) -> utils::Result<PathBuf> {
     Ok(dir)
 }
 
-/// Look up a cached completion if allowed; otherwise call the API and persist
-/// the result.
+/// Look up a cached completion if allowed; otherwise walk the provider
+/// chain and persist the result.
+///
+/// With `stream` configured, the message is rendered on the terminal while it is
+/// generated. The origin is `None` for cached messages.
 async fn generate_message(
-    client: &OpenAI,
+    chain: &Chain,
     cache: &Cache,
-    model_name: &str,
-    logs: &[String],
-    diffs: &[String],
+    config: &Config,
+    system_prompt: &str,
+    user_prompt: &str,
     no_cache: bool,
-) -> utils::Result<String> {
-    let key = Cache::build_key(model_name, SYSTEM_PROMPT, diffs, logs);
+) -> utils::Result<(String, Option<Origin>)> {
+    // The user prompt carries the logs, the diffstat and the fitted diff.
+    let key = Cache::build_key(
+        &config.model,
+        &config.params(),
+        system_prompt,
+        &[user_prompt.to_string()],
+        &[],
+    );
     debug!("cache key: {key}");
 
     if no_cache {
         trace!("--no-cache enabled, skipping cache lookup");
     } else if let Some(cached) = cache.get(&key) {
         info!("reusing cached commit message (key: {key})");
-        return Ok(cached);
+        return Ok((cached, None));
     }
 
-    let fresh = request_completion(client, model_name, logs, diffs).await?;
+    let live = if config.stream {
+        LiveOutput::open()
+    } else {
+        None
+    };
+    if config.stream && live.is_none() {
+        debug!("no terminal available, generating without live output");
+    }
+    let sink = live.as_ref().map(|l| l as &dyn Sink);
+    let result = chain.generate(system_prompt, user_prompt, sink).await;
+    if let Some(live) = &live {
+        live.finish();
+    }
+    let (completion, origin) = result?;
+    if let Some(usage) = &completion.usage {
+        info!("token usage: {usage}");
+    }
     if !no_cache {
-        cache.put(&key, &fresh);
+        cache.put(&key, &completion.text);
     }
-    Ok(fresh)
+    Ok((completion.text, Some(origin)))
 }
 
 /// Push the rendered commit message onto the system clipboard.
@@ -177,17 +321,40 @@ fn copy_to_clipboard(message: &GitMessage) -> utils::Result<()> {
 }
 
 /// Confirm with the user (unless `--yes`) and create the commit.
-fn run_commit_flow(repository: &Repository, message: &GitMessage, yes: bool) -> utils::Result<()> {
+///
+/// With `--all`, the described changes are staged right before committing.
+/// With `--amend`, HEAD is rewritten instead of getting a new child.
+fn run_commit_flow(
+    repository: &Repository,
+    message: &GitMessage,
+    spec: &DiffSpec,
+    yes: bool,
+) -> utils::Result<()> {
     trace!("commit option is enabled, will commit the changes directly to the repository");
+    let amend = spec.scope == Scope::Amend;
 
     let should_commit = yes || {
         cliclack::intro(format!("{PKG_NAME} v{PKG_VERSION}"))?;
-        cliclack::confirm("Are you sure to commit with generated message below?").interact()?
+        let question = if amend {
+            "Are you sure to amend the last commit with generated message below?"
+        } else {
+            "Are you sure to commit with generated message below?"
+        };
+        cliclack::confirm(question).interact()?
     };
 
     if should_commit {
-        match repository.commit(message) {
+        let result = match &spec.scope {
+            Scope::All { untracked } => repository
+                .stage(&spec.pathspecs, *untracked)
+                .and_then(|()| repository.commit(message)),
+            Scope::Amend => repository.amend(message),
+            _ => repository.commit(message),
+        };
+        match result {
+            Ok(oid) if amend => cliclack::note("Amend successful, last commit ID:", oid)?,
             Ok(oid) => cliclack::note("Commit successful, last commit ID:", oid)?,
+            Err(e) if amend => cliclack::note("Amend failed", e)?,
             Err(e) => cliclack::note("Commit failed", e)?,
         }
     }
@@ -214,29 +381,8 @@ fn init_logging(verbose: bool) {
 }
 
 /// Verify the configured model is reachable.
-async fn check_model_availability(client: &OpenAI, model_name: &str) -> utils::Result<()> {
-    client.check_model(model_name).await?;
+async fn check_model_availability(client: &dyn Provider, model_name: &str) -> utils::Result<()> {
+    client.check(model_name).await?;
     println!("the model name `{model_name}` is available, {PKG_NAME} is ready for use!");
     Ok(())
 }
-
-/// Build the chat request and call the OpenAI API, returning the raw response.
-async fn request_completion(
-    client: &OpenAI,
-    model_name: &str,
-    logs: &[String],
-    diffs: &[String],
-) -> utils::Result<String> {
-    let content = OpenAI::prompt(logs, diffs)?;
-    let messages = vec![
-        ChatCompletionRequestSystemMessageArgs::default()
-            .content(SYSTEM_PROMPT)
-            .build()?
-            .into(),
-        ChatCompletionRequestUserMessageArgs::default()
-            .content(content)
-            .build()?
-            .into(),
-    ];
-    Ok(client.chat(model_name, messages).await?)
-}
@@ -0,0 +1,248 @@
+/*!
+ * Copyright (c) 2026 mingcheng <mingcheng@apache.org>
+ *
+ * This source code is licensed under the MIT License,
+ * which is located in the LICENSE file in the source tree's root directory.
+ *
+ * Prompt rendering shared by all providers.
+ *
+ * File: prompt.rs
+ * Author: mingcheng <mingcheng@apache.org>
+ * File Created: 2026-10-16 15:20:05
+ *
+ * Modified By: mingcheng <mingcheng@apache.org>
+ * Last Modified: 2026-10-16 15:20:05
+ */
+
+use crate::git::diff::Scope;
+use askama::Template;
+use std::error::Error;
+
+/// Everything in the user prompt besides the diff.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Context {
+    /// Commit messages used as style reference.
+    pub logs: Vec<String>,
+    /// The changes make up the first commit of the repository, so there is
+    /// no history to refer to.
+    pub initial: bool,
+    /// Diffstat of the whole change, shown before the hunks.
+    pub stat: Vec<String>,
+    /// Which changes the diff covers.
+    pub scope: Scope,
+}
+
+#[derive(Template)]
+#[template(path = "user.txt")]
+struct PromptTemplate<'a> {
+    logs: &'a str,
+    stat: &'a str,
+    scope: &'a str,
+    diffs: &'a str,
+    omitted: &'a str,
+}
+
+/// The user prompt of a first commit, which has no history to show.
+#[derive(Template)]
+#[template(path = "initial.txt")]
+struct InitialTemplate<'a> {
+    stat: &'a str,
+    scope: &'a str,
+    diffs: &'a str,
+    omitted: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "system.txt")]
+struct SystemTemplate<'a> {
+    language: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "summary.txt")]
+struct SummaryTemplate;
+
+/// Render the system prompt for the given output language.
+pub fn system(language: &str) -> Result<String, Box<dyn Error>> {
+    Ok(SystemTemplate { language }.render()?)
+}
+
+/// Render the system prompt of per-file summary requests.
+pub fn summary() -> Result<String, Box<dyn Error>> {
+    Ok(SummaryTemplate.render()?)
+}
+
+/// Render the user prompt from its context and the diff.
+///
+/// `omitted` lists the parts of the diff that were trimmed to fit the
+/// token budget, one line per file. The first commit of a repository gets
+/// a variant without the history section.
+pub fn user(
+    context: &Context,
+    diff: &[String],
+    omitted: &[String],
+) -> Result<String, Box<dyn Error>> {
+    let (stat, scope, diffs, omitted) = (
+        &context.stat.join("\n"),
+        &context.scope.describe(),
+        &diff.join("\n"),
+        &omitted.join("\n"),
+    );
+    if context.initial {
+        return Ok(InitialTemplate {
+            stat,
+            scope,
+            diffs,
+            omitted,
+        }
+        .render()?);
+    }
+
+    let template = PromptTemplate {
+        logs: &context.logs.join("\n"),
+        stat,
+        scope,
+        diffs,
+        omitted,
+    };
+    Ok(template.render()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::repository::Repository;
+    use tracing::error;
+
+    fn setup_repo() -> Result<Repository, Box<dyn Error>> {
+        let repo_path = std::env::var("TEST_REPO_PATH")
+            .map_err(|_| "TEST_REPO_PATH environment variable not set")?;
+        if repo_path.is_empty() {
+            return Err("Please specify the repository path".into());
+        }
+
+        Repository::new(&repo_path)
+    }
+
+    #[test]
+    fn test_prompt() {
+        let repo = setup_repo();
+        if repo.is_err() {
+            error!("Please specify the repository path");
+            return;
+        }
+
+        assert!(repo.is_ok());
+        let repo = repo.unwrap();
+
+        let diffs = repo.get_diff(&crate::git::diff::DiffSpec::default());
+        assert!(diffs.is_ok());
+
+        let logs = repo.get_logs(
+            &crate::git::history::History::recent(5),
+            &crate::git::diff::Scope::Staged,
+        );
+        assert!(logs.is_ok());
+
+        let diff_content = diffs.unwrap();
+        assert!(!diff_content.is_empty());
+
+        let logs_content = logs.unwrap();
+        assert!(!logs_content.is_empty());
+
+        let context = Context {
+            logs: logs_content,
+            ..Context::default()
+        };
+        let result = user(&context, &diff_content, &[]).unwrap();
+        assert!(!result.is_empty());
+        assert!(!result.contains("Omitted Changes"));
+    }
+
+    #[test]
+    fn test_user_prompt_lists_omitted_changes() {
+        let omitted = vec!["- vendor/x.js: all changes omitted (900 lines)".to_string()];
+        let context = Context {
+            logs: vec!["feat: a".to_string()],
+            ..Context::default()
+        };
+        let prompt = user(&context, &["diff --git".to_string()], &omitted).unwrap();
+        assert!(prompt.contains("### Omitted Changes"));
+        assert!(prompt.contains("- vendor/x.js: all changes omitted (900 lines)"));
+    }
+
+    #[test]
+    fn test_user_prompt_shows_stat_before_diff() {
+        let stat = vec![
+            "M src/lib.rs | +1 -1".to_string(),
+            "1 file changed, 1 insertion(+), 1 deletion(-)".to_string(),
+        ];
+        let mut context = Context {
+            logs: vec!["feat: a".to_string()],
+            stat,
+            ..Context::default()
+        };
+        let prompt = user(&context, &["diff --git".to_string()], &[]).unwrap();
+        let summary = prompt.find("### Change Summary").unwrap();
+        assert!(summary < prompt.find("### Changes").unwrap());
+        assert!(prompt.contains("M src/lib.rs | +1 -1"));
+
+        context.stat.clear();
+        let prompt = user(&context, &["diff --git".to_string()], &[]).unwrap();
+        assert!(!prompt.contains("### Change Summary"));
+    }
+
+    #[test]
+    fn test_user_prompt_of_the_initial_commit() {
+        let context = Context {
+            initial: true,
+            stat: vec!["A README.md | +1 -0".to_string()],
+            ..Context::default()
+        };
+        let prompt = user(&context, &["diff --git".to_string()], &[]).unwrap();
+        assert!(prompt.starts_with("### Initial Commit"));
+        assert!(!prompt.contains("### Recent Commit History"));
+        assert!(prompt.contains("A README.md | +1 -0"));
+        assert!(prompt.contains("### Changes"));
+
+        // No usable history is not the same as no history at all.
+        let prompt = user(&Context::default(), &["diff --git".to_string()], &[]).unwrap();
+        assert!(!prompt.contains("### Initial Commit"));
+        assert!(!prompt.contains("### Recent Commit History"));
+        assert!(prompt.starts_with("### Changes"));
+
+        let context = Context {
+            logs: vec!["feat: a".to_string()],
+            ..Context::default()
+        };
+        let prompt = user(&context, &[], &[]).unwrap();
+        assert!(prompt.starts_with("### Recent Commit History"));
+    }
+
+    #[test]
+    fn test_user_prompt_names_the_scope() {
+        let context = Context {
+            scope: Scope::Revision("abc1234".into()),
+            ..Context::default()
+        };
+        let prompt = user(&context, &["diff --git".to_string()], &[]).unwrap();
+        assert!(prompt.starts_with(
+            "### Changes\nThe changes of commit `abc1234` against its first parent.\n```"
+        ));
+        assert!(!prompt.contains("staged"));
+
+        let context = Context {
+            initial: true,
+            ..context
+        };
+        let prompt = user(&context, &[], &[]).unwrap();
+        assert!(prompt.contains("### Changes\nThe changes of commit `abc1234`"));
+    }
+
+    #[test]
+    fn test_system_prompt_language() {
+        let prompt = system("Chinese").unwrap();
+        assert!(prompt.contains("Conventional Commit messages in Chinese."));
+        assert!(!prompt.contains("{{"));
+    }
+}
@@ -0,0 +1,338 @@
+/*!
+ * Copyright (c) 2026 mingcheng <mingcheng@apache.org>
+ *
+ * This source code is licensed under the MIT License,
+ * which is located in the LICENSE file in the source tree's root directory.
+ *
+ * Native Anthropic Messages API backend.
+ *
+ * File: anthropic.rs
+ * Author: mingcheng <mingcheng@apache.org>
+ * File Created: 2026-10-16 16:05:12
+ *
+ * Modified By: mingcheng <mingcheng@apache.org>
+ * Last Modified: 2026-10-16 16:05:12
+ */
+
+use crate::config::Config;
+use crate::provider::{self, Completion, Error, Provider, Request, Usage};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+
+/// Default API endpoint when `api_base` is not configured.
+pub const DEFAULT_API_BASE: &str = "https://api.anthropic.com/v1";
+
+/// Value of the required `anthropic-version` header.
+const API_VERSION: &str = "2023-06-01";
+
+/// `max_tokens` is mandatory for the Messages API; commit messages are short.
+const DEFAULT_MAX_TOKENS: u32 = 1024;
+
+/// Client for `POST /v1/messages`.
+pub struct Anthropic {
+    http: reqwest::Client,
+    api_base: String,
+    api_key: String,
+}
+
+#[derive(Serialize)]
+struct MessagesRequest<'a> {
+    model: &'a str,
+    max_tokens: u32,
+    system: &'a str,
+    messages: Vec<Message<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f64>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    stop_sequences: &'a [String],
+}
+
+#[derive(Serialize)]
+struct Message<'a> {
+    role: &'static str,
+    content: Vec<ContentBlock<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentBlock<'a> {
+    Text { text: &'a str },
+}
+
+#[derive(Deserialize)]
+struct MessagesResponse {
+    content: Vec<ResponseBlock>,
+    usage: Option<MessagesUsage>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ResponseBlock {
+    Text {
+        text: String,
+    },
+    /// Thinking and tool-use blocks carry no commit message text.
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct MessagesUsage {
+    input_tokens: u64,
+    output_tokens: u64,
+}
+
+/// One page of the model list.
+#[derive(Deserialize)]
+struct ModelList {
+    data: Vec<ModelInfo>,
+    #[serde(default)]
+    has_more: bool,
+    last_id: Option<String>,
+}
+
+/// Models requested per page, the maximum the API allows.
+const MODELS_PAGE_SIZE: &str = "1000";
+
+#[derive(Deserialize)]
+struct ModelInfo {
+    id: String,
+}
+
+impl Anthropic {
+    /// Create a client from the effective configuration.
+    pub fn new(config: &Config) -> Result<Self, Box<dyn std::error::Error>> {
+        let http = provider::http_client_builder(config)
+            .build()
+            .map_err(|e| format!("failed to build HTTP client: {e}"))?;
+        Ok(Self {
+            http,
+            api_base: config
+                .api_base
+                .clone()
+                .unwrap_or_else(|| DEFAULT_API_BASE.to_string()),
+            api_key: config.api_token()?,
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.http
+            .request(method, provider::endpoint(&self.api_base, path))
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", API_VERSION)
+    }
+}
+
+#[async_trait]
+impl Provider for Anthropic {
+    fn name(&self) -> &'static str {
+        "anthropic"
+    }
+
+    async fn generate(&self, request: &Request) -> provider::Result<Completion> {
+        let params = &request.params;
+        if params.seed.is_some() {
+            debug!("the Messages API does not support `seed`, ignoring it");
+        }
+        let body = MessagesRequest {
+            model: &request.model,
+            max_tokens: params.max_completion_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            system: &request.system,
+            messages: vec![Message {
+                role: "user",
+                content: vec![ContentBlock::Text {
+                    text: &request.prompt,
+                }],
+            }],
+            temperature: params.temperature,
+            top_p: params.top_p,
+            stop_sequences: &params.stop,
+        };
+
+        trace!("✨ Using model: {}", request.model);
+        let response: MessagesResponse =
+            provider::send_json(self.request(reqwest::Method::POST, "messages").json(&body))
+                .await?;
+
+        let text: Vec<String> = response
+            .content
+            .into_iter()
+            .filter_map(|block| match block {
+                ResponseBlock::Text { text } => Some(text),
+                ResponseBlock::Other => None,
+            })
+            .collect();
+        if text.is_empty() {
+            return Err(Error::Other("response contained no text".to_string()));
+        }
+
+        let usage = response.usage.map(|u| Usage {
+            prompt_tokens: u.input_tokens,
+            completion_tokens: u.output_tokens,
+            total_tokens: u.input_tokens + u.output_tokens,
+            reasoning_tokens: None,
+        });
+
+        Ok(Completion {
+            text: text.join(""),
+            usage,
+        })
+    }
+
+    async fn list_models(&self) -> provider::Result<Vec<String>> {
+        let mut models = Vec::new();
+        let mut after: Option<String> = None;
+        loop {
+            let mut request = self
+                .request(reqwest::Method::GET, "models")
+                .query(&[("limit", MODELS_PAGE_SIZE)]);
+            if let Some(after) = &after {
+                request = request.query(&[("after_id", after)]);
+            }
+            let page: ModelList = provider::send_json(request).await?;
+            models.extend(page.data.into_iter().map(|m| m.id));
+            match page.last_id {
+                Some(last) if page.has_more => after = Some(last),
+                _ => return Ok(models),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::mock::{MockServer, Reply};
+    use crate::provider::{Params, ProviderKind};
+
+    fn config(base: &str) -> Config {
+        Config {
+            provider: ProviderKind::Anthropic,
+            api_base: Some(format!("{base}/v1")),
+            api_token: "sk-ant-test".to_string(),
+            ..Config::default()
+        }
+    }
+
+    fn request() -> Request {
+        Request {
+            model: "claude-sonnet-4-5".into(),
+            system: "be terse".into(),
+            prompt: "diff".into(),
+            ..Request::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn generate_uses_messages_api_shape() {
+        let server = MockServer::start(vec![Reply::json(
+            r#"{"id":"msg_1","type":"message","role":"assistant",
+                "content":[{"type":"thinking","thinking":"..."},
+                           {"type":"text","text":"fix: y\n\n- z"}],
+                "usage":{"input_tokens":12,"output_tokens":8}}"#,
+        )])
+        .await;
+        let client = Anthropic::new(&config(server.url())).unwrap();
+
+        let completion = client.generate(&request()).await.unwrap();
+        assert_eq!(completion.text, "fix: y\n\n- z");
+        let usage = completion.usage.unwrap();
+        assert_eq!((usage.prompt_tokens, usage.total_tokens), (12, 20));
+
+        let sent = &server.requests()[0];
+        assert_eq!(sent.method, "POST");
+        assert_eq!(sent.path, "/v1/messages");
+        assert_eq!(sent.header("x-api-key"), Some("sk-ant-test"));
+        assert_eq!(sent.header("anthropic-version"), Some(API_VERSION));
+        assert!(sent.header("authorization").is_none());
+        let body = sent.json();
+        assert_eq!(body["system"], "be terse");
+        assert_eq!(body["max_tokens"], DEFAULT_MAX_TOKENS);
+        assert_eq!(body["messages"][0]["role"], "user");
+        assert_eq!(body["messages"][0]["content"][0]["type"], "text");
+        assert_eq!(body["messages"][0]["content"][0]["text"], "diff");
+        assert!(body.get("temperature").is_none());
+        assert!(body.get("stop_sequences").is_none());
+    }
+
+    #[tokio::test]
+    async fn generate_maps_generation_params() {
+        let server = MockServer::start(vec![Reply::json(
+            r#"{"content":[{"type":"text","text":"fix: y"}]}"#,
+        )])
+        .await;
+        let client = Anthropic::new(&config(server.url())).unwrap();
+
+        let mut request = request();
+        request.params = Params {
+            temperature: Some(0.3),
+            max_completion_tokens: Some(200),
+            stop: vec!["END".into()],
+            ..Params::default()
+        };
+        client.generate(&request).await.unwrap();
+
+        let body = server.requests()[0].json();
+        assert_eq!(body["max_tokens"], 200);
+        assert_eq!(body["temperature"], 0.3);
+        assert_eq!(body["stop_sequences"][0], "END");
+    }
+
+    #[tokio::test]
+    async fn error_status_carries_api_message() {
+        let server = MockServer::start(vec![Reply::status(
+            401,
+            r#"{"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#,
+        )])
+        .await;
+        let client = Anthropic::new(&config(server.url())).unwrap();
+
+        match client.generate(&request()).await {
+            Err(Error::Status {
+                status, message, ..
+            }) => {
+                assert_eq!(status, 401);
+                assert_eq!(message, "invalid x-api-key");
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn check_lists_models() {
+        let server = MockServer::start(vec![Reply::json(
+            r#"{"data":[{"id":"claude-sonnet-4-5","type":"model"}],"has_more":false}"#,
+        )])
+        .await;
+        let client = Anthropic::new(&config(server.url())).unwrap();
+        assert!(client.check("claude-sonnet-4-5").await.is_ok());
+        assert!(client.check("claude-2").await.is_err());
+        assert_eq!(server.requests()[0].path, "/v1/models?limit=1000");
+    }
+
+    #[tokio::test]
+    async fn check_follows_pagination() {
+        let server = MockServer::start(vec![
+            Reply::json(
+                r#"{"data":[{"id":"claude-sonnet-4-5"}],"has_more":true,
+                    "first_id":"claude-sonnet-4-5","last_id":"claude-sonnet-4-5"}"#,
+            ),
+            Reply::json(
+                r#"{"data":[{"id":"claude-3-haiku"}],"has_more":false,
+                    "first_id":"claude-3-haiku","last_id":"claude-3-haiku"}"#,
+            ),
+        ])
+        .await;
+        let client = Anthropic::new(&config(server.url())).unwrap();
+        assert!(client.check("claude-3-haiku").await.is_ok());
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[1].path,
+            "/v1/models?limit=1000&after_id=claude-sonnet-4-5"
+        );
+    }
+}
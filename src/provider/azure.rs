@@ -0,0 +1,353 @@
+/*!
+ * Copyright (c) 2026 mingcheng <mingcheng@apache.org>
+ *
+ * This source code is licensed under the MIT License,
+ * which is located in the LICENSE file in the source tree's root directory.
+ *
+ * Azure OpenAI backend.
+ *
+ * Azure addresses a deployment rather than a model and authenticates with
+ * either an `api-key` header or a Microsoft Entra ID (AAD) bearer token, so
+ * requests are built here and only the chat completion payloads are shared
+ * with the OpenAI provider.
+ *
+ * File: azure.rs
+ * Author: mingcheng <mingcheng@apache.org>
+ * File Created: 2026-10-16 17:30:44
+ *
+ * Modified By: mingcheng <mingcheng@apache.org>
+ * Last Modified: 2026-10-16 17:30:44
+ */
+
+use crate::config::Config;
+use crate::credential;
+use crate::provider::{self, Completion, Error, Provider, Request, Sink, openai};
+use async_openai::types::chat::CreateChatCompletionResponse;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+
+/// Error codes of a 400 that only reject the one-token probe of
+/// [`Azure::check`], which a working deployment may answer with.
+const PROBE_ERROR_CODES: &[&str] = &[
+    "unsupported_parameter",
+    "unsupported_value",
+    "integer_below_min_value",
+];
+
+/// API version used when `azure.api_version` is not configured.
+pub const DEFAULT_API_VERSION: &str = "2024-10-21";
+
+/// Azure-specific settings, the `[azure]` table.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AzureConfig {
+    /// Resource endpoint, e.g. `https://my-resource.openai.azure.com`.
+    /// Falls back to `api_base`.
+    pub endpoint: Option<String>,
+    /// Deployment name. Falls back to `model`.
+    pub deployment: Option<String>,
+    /// Value of the `api-version` query parameter.
+    pub api_version: Option<String>,
+    /// Command printing a Microsoft Entra ID access token, e.g.
+    /// `az account get-access-token --resource https://cognitiveservices.azure.com --query accessToken -o tsv`.
+    /// Takes precedence over the API key.
+    pub token_command: Option<String>,
+}
+
+/// How requests are authenticated.
+enum Auth {
+    ApiKey(String),
+    Bearer(String),
+}
+
+/// Client for an Azure OpenAI deployment.
+pub struct Azure {
+    http: reqwest::Client,
+    endpoint: String,
+    deployment: String,
+    api_version: String,
+    auth: Auth,
+}
+
+#[derive(Deserialize)]
+struct ModelList {
+    data: Vec<ModelInfo>,
+}
+
+#[derive(Deserialize)]
+struct ModelInfo {
+    id: String,
+}
+
+impl Azure {
+    /// Create a client from the effective configuration.
+    pub fn new(config: &Config) -> Result<Self, Box<dyn std::error::Error>> {
+        let settings = &config.azure;
+        let non_empty = |v: &Option<String>| v.clone().filter(|v| !v.trim().is_empty());
+
+        let endpoint = non_empty(&settings.endpoint)
+            .or_else(|| non_empty(&config.api_base))
+            .ok_or("azure.endpoint is not set")?;
+        let auth = match non_empty(&settings.token_command) {
+            Some(command) => Auth::Bearer(credential::from_command(&command)?),
+            None => Auth::ApiKey(config.api_token()?),
+        };
+
+        let http = provider::http_client_builder(config)
+            .build()
+            .map_err(|e| format!("failed to build HTTP client: {e}"))?;
+        Ok(Self {
+            http,
+            endpoint,
+            deployment: non_empty(&settings.deployment).unwrap_or_else(|| config.model.clone()),
+            api_version: non_empty(&settings.api_version)
+                .unwrap_or_else(|| DEFAULT_API_VERSION.to_string()),
+            auth,
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let builder = self
+            .http
+            .request(method, provider::endpoint(&self.endpoint, path))
+            .query(&[("api-version", &self.api_version)]);
+        match &self.auth {
+            Auth::ApiKey(key) => builder.header("api-key", key),
+            Auth::Bearer(token) => builder.bearer_auth(token),
+        }
+    }
+
+    fn chat_path(&self) -> String {
+        format!("openai/deployments/{}/chat/completions", self.deployment)
+    }
+}
+
+#[async_trait]
+impl Provider for Azure {
+    fn name(&self) -> &'static str {
+        "azure"
+    }
+
+    async fn generate(&self, request: &Request) -> provider::Result<Completion> {
+        let mut body = openai::chat_request(request)?;
+        // Azure ignores the model field, the deployment decides.
+        body.model = self.deployment.clone();
+
+        trace!("✨ Using deployment: {}", self.deployment);
+        let response: CreateChatCompletionResponse = provider::send_json(
+            self.request(reqwest::Method::POST, &self.chat_path())
+                .json(&body),
+        )
+        .await?;
+        Ok(openai::completion(response))
+    }
+
+    async fn generate_stream(
+        &self,
+        request: &Request,
+        sink: &dyn Sink,
+    ) -> provider::Result<Completion> {
+        let mut body = openai::chat_request(request)?;
+        body.model = self.deployment.clone();
+        openai::enable_streaming(&mut body);
+
+        trace!("✨ Streaming from deployment: {}", self.deployment);
+        openai::stream_completion(
+            self.request(reqwest::Method::POST, &self.chat_path())
+                .json(&body),
+            sink,
+        )
+        .await
+    }
+
+    /// Base models available to the resource. Deployments are not listed by
+    /// the data-plane API.
+    async fn list_models(&self) -> provider::Result<Vec<String>> {
+        let list: ModelList =
+            provider::send_json(self.request(reqwest::Method::GET, "openai/models")).await?;
+        Ok(list.data.into_iter().map(|m| m.id).collect())
+    }
+
+    /// Validate the deployment with a one-token request. The `model`
+    /// argument is ignored because Azure resolves the deployment instead.
+    async fn check(&self, _model: &str) -> provider::Result<()> {
+        let mut body = openai::chat_request(&Request {
+            model: self.deployment.clone(),
+            system: String::new(),
+            prompt: "ping".to_string(),
+            ..Request::default()
+        })?;
+        body.max_completion_tokens = Some(1);
+
+        let response = self
+            .request(reqwest::Method::POST, &self.chat_path())
+            .json(&body)
+            .send()
+            .await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body = response.bytes().await?;
+        let message = provider::error_message(&body);
+        match status.as_u16() {
+            // The deployment exists but rejected the tiny request, e.g.
+            // because one token is too few for a reasoning model.
+            400 if rejects_probe(&body) => {
+                debug!("deployment answered the probe with 400: {message}");
+                Ok(())
+            }
+            404 => Err(Error::Other(format!(
+                "deployment `{}` not found at {}",
+                self.deployment, self.endpoint
+            ))),
+            status => Err(Error::Status {
+                status,
+                message,
+                retry_after: None,
+            }),
+        }
+    }
+}
+
+/// Whether a 400 body objects to the probe's parameters rather than to the
+/// deployment, the API version or the request as such.
+fn rejects_probe(body: &[u8]) -> bool {
+    let Ok(value) = serde_json::from_slice::<serde_json::Value>(body) else {
+        return false;
+    };
+    let error = &value["error"];
+    let code = error["code"].as_str().unwrap_or_default();
+    let param = error["param"].as_str().unwrap_or_default();
+    PROBE_ERROR_CODES.contains(&code) || matches!(param, "max_tokens" | "max_completion_tokens")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ProviderKind;
+    use crate::provider::mock::{MockServer, Reply};
+
+    fn config(base: &str, azure: AzureConfig) -> Config {
+        Config {
+            provider: ProviderKind::Azure,
+            model: "gpt-5".to_string(),
+            api_token: "azure-key".to_string(),
+            azure: AzureConfig {
+                endpoint: Some(base.to_string()),
+                ..azure
+            },
+            ..Config::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn generate_targets_deployment_with_api_key() {
+        let server = MockServer::start(vec![Reply::json(
+            r#"{"id":"1","object":"chat.completion","created":0,"model":"gpt-5",
+                "choices":[{"index":0,"finish_reason":"stop",
+                  "message":{"role":"assistant","content":"chore: z"}}],
+                "usage":{"prompt_tokens":3,"completion_tokens":2,"total_tokens":5}}"#,
+        )])
+        .await;
+        let azure = AzureConfig {
+            deployment: Some("commit-writer".into()),
+            api_version: Some("2025-01-01-preview".into()),
+            ..AzureConfig::default()
+        };
+        let client = Azure::new(&config(server.url(), azure)).unwrap();
+
+        let completion = client
+            .generate(&Request {
+                model: "gpt-5".into(),
+                system: "sys".into(),
+                prompt: "diff".into(),
+                ..Request::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(completion.text, "chore: z");
+        assert_eq!(completion.usage.unwrap().total_tokens, 5);
+
+        let sent = &server.requests()[0];
+        assert_eq!(
+            sent.path,
+            "/openai/deployments/commit-writer/chat/completions?api-version=2025-01-01-preview"
+        );
+        assert_eq!(sent.header("api-key"), Some("azure-key"));
+        assert!(sent.header("authorization").is_none());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn token_command_uses_bearer_auth() {
+        let server = MockServer::start(vec![Reply::status(
+            400,
+            r#"{"error":{"code":"unsupported_value","param":"max_completion_tokens","message":"x"}}"#,
+        )])
+        .await;
+        let azure = AzureConfig {
+            token_command: Some("echo aad-token-for-test".into()),
+            ..AzureConfig::default()
+        };
+        let client = Azure::new(&config(server.url(), azure)).unwrap();
+
+        assert!(client.check("ignored").await.is_ok());
+        let sent = &server.requests()[0];
+        assert_eq!(
+            sent.path,
+            format!("/openai/deployments/gpt-5/chat/completions?api-version={DEFAULT_API_VERSION}")
+        );
+        assert_eq!(
+            sent.header("authorization"),
+            Some("Bearer aad-token-for-test")
+        );
+        assert!(sent.header("api-key").is_none());
+        assert_eq!(sent.json()["max_completion_tokens"], 1);
+    }
+
+    #[tokio::test]
+    async fn check_reports_missing_deployment() {
+        let server = MockServer::start(vec![Reply::status(
+            404,
+            r#"{"error":{"code":"DeploymentNotFound","message":"The API deployment for this resource does not exist."}}"#,
+        )])
+        .await;
+        let client = Azure::new(&config(server.url(), AzureConfig::default())).unwrap();
+
+        let err = client.check("gpt-5").await.unwrap_err().to_string();
+        assert!(err.contains("deployment `gpt-5` not found"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn check_rejects_other_bad_requests() {
+        let server = MockServer::start(vec![Reply::status(
+            400,
+            r#"{"error":{"code":"BadRequest","message":"API version not supported"}}"#,
+        )])
+        .await;
+        let azure = AzureConfig {
+            api_version: Some("1999-01-01".into()),
+            ..AzureConfig::default()
+        };
+        let client = Azure::new(&config(server.url(), azure)).unwrap();
+
+        let err = client.check("gpt-5").await.unwrap_err().to_string();
+        assert!(err.contains("API version not supported"), "{err}");
+        assert!(
+            server.requests()[0]
+                .path
+                .ends_with("api-version=1999-01-01")
+        );
+    }
+
+    #[test]
+    fn endpoint_is_required() {
+        let config = Config {
+            provider: ProviderKind::Azure,
+            ..Config::default()
+        };
+        assert!(Azure::new(&config).is_err());
+    }
+}
@@ -0,0 +1,227 @@
+/*!
+ * Copyright (c) 2026 mingcheng <mingcheng@apache.org>
+ *
+ * This source code is licensed under the MIT License,
+ * which is located in the LICENSE file in the source tree's root directory.
+ *
+ * Ordered provider/model fallback chain.
+ *
+ * The primary configuration is tried first, then every `[[fallback]]`
+ * entry in order. Only retryable errors (timeouts, rate limits, 5xx) move
+ * on to the next entry; anything else, such as a rejected token, is
+ * reported immediately. Each entry is retried according to its own retry
+ * policy before moving on. Fallback providers are built lazily so that
+ * their token commands only run when they are actually needed.
+ *
+ * File: fallback.rs
+ * Author: mingcheng <mingcheng@apache.org>
+ * File Created: 2026-10-16 18:55:31
+ *
+ * Modified By: mingcheng <mingcheng@apache.org>
+ * Last Modified: 2026-10-16 18:55:31
+ */
+
+use crate::config::Config;
+use crate::provider::{self, Completion, Origin, Request, Sink, retry};
+use std::error::Error;
+use tracing::{info, warn};
+
+/// The primary configuration followed by its fallbacks.
+pub struct Chain {
+    links: Vec<Config>,
+}
+
+impl Chain {
+    /// Resolve the configuration of every link up front, so a broken
+    /// fallback entry is reported before any request is made.
+    pub fn new(config: &Config) -> Result<Self, Box<dyn Error>> {
+        let mut links = vec![config.clone()];
+        for fallback in &config.fallback {
+            links.push(config.for_fallback(fallback)?);
+        }
+        Ok(Self { links })
+    }
+
+    /// Generate a completion with the first link that succeeds.
+    ///
+    /// With a `sink`, text is streamed into it as it arrives; the sink is
+    /// reset before every retry and fallback attempt.
+    ///
+    /// Returns the completion and the provider and model that produced it.
+    pub async fn generate(
+        &self,
+        system: &str,
+        prompt: &str,
+        sink: Option<&dyn Sink>,
+    ) -> Result<(Completion, Origin), Box<dyn Error>> {
+        let mut last_error: Option<Box<dyn Error>> = None;
+        // Setup errors of fallbacks are only reported when no provider
+        // failed, so they never hide the error that caused the fallback.
+        let mut setup_error: Option<Box<dyn Error>> = None;
+
+        for (index, config) in self.links.iter().enumerate() {
+            let is_last = index + 1 == self.links.len();
+            let client = match provider::build(config) {
+                Ok(client) => client,
+                // Only the primary provider's setup errors are fatal; a
+                // misconfigured fallback should not hide the original error.
+                Err(e) if index > 0 => {
+                    warn!(
+                        "skipping fallback {:?} ({}): {e}",
+                        config.provider, config.model
+                    );
+                    setup_error.get_or_insert(e);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            let origin = Origin {
+                provider: client.name().to_string(),
+                model: config.model.clone(),
+            };
+
+            let request = Request {
+                model: config.model.clone(),
+                system: system.to_string(),
+                prompt: prompt.to_string(),
+                params: config.params(),
+            };
+            let attempt = || async {
+                match sink {
+                    Some(sink) => {
+                        sink.reset();
+                        client.generate_stream(&request, sink).await
+                    }
+                    None => client.generate(&request).await,
+                }
+            };
+            match retry::run(&config.retry, attempt).await {
+                Ok(completion) => {
+                    info!("message generated by {origin}");
+                    return Ok((completion, origin));
+                }
+                Err(e) if e.is_retryable() && !is_last => {
+                    warn!("{origin} failed: {e}; trying the next fallback");
+                    last_error = Some(format!("{origin} failed: {e}").into());
+                }
+                Err(e) => return Err(format!("{origin} failed: {e}").into()),
+            }
+        }
+
+        Err(last_error
+            .or(setup_error)
+            .unwrap_or_else(|| "no provider configured".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Fallback;
+    use crate::provider::mock::{MockServer, Reply};
+    use crate::provider::retry::RetryConfig;
+    use crate::provider::{ProviderKind, Usage};
+    use std::collections::BTreeMap;
+
+    const OK: &str = r#"{"id":"1","object":"chat.completion","created":0,"model":"m",
+        "choices":[{"index":0,"finish_reason":"stop",
+          "message":{"role":"assistant","content":"fix: a\n\nb"}}]}"#;
+
+    fn config(primary: &str, fallbacks: Vec<(&str, &str)>) -> Config {
+        let mut profiles = BTreeMap::new();
+        let mut fallback = Vec::new();
+        for (i, (base, model)) in fallbacks.into_iter().enumerate() {
+            let name = format!("f{i}");
+            let mut profile = toml::Table::new();
+            profile.insert("api_base".into(), format!("{base}/v1").into());
+            profiles.insert(name.clone(), profile);
+            fallback.push(Fallback {
+                profile: Some(name),
+                model: Some(model.to_string()),
+                ..Fallback::default()
+            });
+        }
+        Config {
+            provider: ProviderKind::OpenAI,
+            api_base: Some(format!("{primary}/v1")),
+            api_token: "sk-test".into(),
+            retry: RetryConfig {
+                max_retries: 0,
+                ..RetryConfig::default()
+            },
+            profiles,
+            fallback,
+            ..Config::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn falls_back_on_server_errors() {
+        let primary = MockServer::start(vec![Reply::status(503, "unavailable")]).await;
+        let backup = MockServer::start(vec![Reply::json(OK)]).await;
+        let chain =
+            Chain::new(&config(primary.url(), vec![(backup.url(), "backup-model")])).unwrap();
+
+        let (completion, origin) = chain.generate("sys", "diff", None).await.unwrap();
+        assert_eq!(completion.text, "fix: a\n\nb");
+        assert_eq!(completion.usage, None::<Usage>);
+        assert_eq!(
+            origin,
+            Origin {
+                provider: "openai".into(),
+                model: "backup-model".into(),
+            }
+        );
+        assert_eq!(primary.requests().len(), 1);
+        assert_eq!(backup.requests()[0].json()["model"], "backup-model");
+    }
+
+    #[tokio::test]
+    async fn stops_on_non_retryable_errors() {
+        let primary = MockServer::start(vec![Reply::status(401, "bad key")]).await;
+        let backup = MockServer::start(vec![Reply::json(OK)]).await;
+        let chain =
+            Chain::new(&config(primary.url(), vec![(backup.url(), "backup-model")])).unwrap();
+
+        let err = chain.generate("sys", "diff", None).await.unwrap_err();
+        assert!(err.to_string().contains("HTTP 401"), "{err}");
+        assert!(backup.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn reports_the_provider_error_over_a_broken_fallback() {
+        let primary = MockServer::start(vec![Reply::status(503, "unavailable")]).await;
+        let mut config = config(primary.url(), vec![("http://127.0.0.1:9", "backup-model")]);
+        config.profiles.get_mut("f0").unwrap().insert(
+            "api_token_env".into(),
+            "AIGITCOMMIT_TEST_UNSET_TOKEN".into(),
+        );
+        let chain = Chain::new(&config).unwrap();
+
+        let err = chain
+            .generate("sys", "diff", None)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("HTTP 503"), "{err}");
+        assert_eq!(primary.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn reports_the_last_error_when_all_fail() {
+        let primary = MockServer::start(vec![Reply::status(500, "boom")]).await;
+        let backup = MockServer::start(vec![Reply::status(502, "still down")]).await;
+        let chain =
+            Chain::new(&config(primary.url(), vec![(backup.url(), "backup-model")])).unwrap();
+
+        let err = chain
+            .generate("sys", "diff", None)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("backup-model") && err.contains("HTTP 502"),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,366 @@
+/*!
+ * Copyright (c) 2026 mingcheng <mingcheng@apache.org>
+ *
+ * This source code is licensed under the MIT License,
+ * which is located in the LICENSE file in the source tree's root directory.
+ *
+ * Google Gemini `generateContent` backend.
+ *
+ * File: gemini.rs
+ * Author: mingcheng <mingcheng@apache.org>
+ * File Created: 2026-10-16 18:12:06
+ *
+ * Modified By: mingcheng <mingcheng@apache.org>
+ * Last Modified: 2026-10-16 18:12:06
+ */
+
+use crate::config::Config;
+use crate::provider::{self, Completion, Error, Provider, Request, Usage};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+
+/// Default API endpoint when `api_base` is not configured.
+pub const DEFAULT_API_BASE: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+/// Client for `POST /v1beta/models/{model}:generateContent`.
+pub struct Gemini {
+    http: reqwest::Client,
+    api_base: String,
+    api_key: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerateRequest<'a> {
+    system_instruction: Content<'a>,
+    contents: Vec<Content<'a>>,
+    generation_config: GenerationConfig<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerationConfig<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_output_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    stop_sequences: &'a [String],
+}
+
+#[derive(Serialize)]
+struct Content<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    role: Option<&'static str>,
+    parts: Vec<Part<'a>>,
+}
+
+#[derive(Serialize)]
+struct Part<'a> {
+    text: &'a str,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerateResponse {
+    #[serde(default)]
+    candidates: Vec<Candidate>,
+    usage_metadata: Option<UsageMetadata>,
+    prompt_feedback: Option<PromptFeedback>,
+}
+
+#[derive(Deserialize)]
+struct Candidate {
+    content: Option<CandidateContent>,
+}
+
+#[derive(Deserialize)]
+struct CandidateContent {
+    #[serde(default)]
+    parts: Vec<ResponsePart>,
+}
+
+#[derive(Deserialize)]
+struct ResponsePart {
+    text: Option<String>,
+    /// Set on thought summaries, which are not part of the answer.
+    #[serde(default)]
+    thought: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageMetadata {
+    #[serde(default)]
+    prompt_token_count: u64,
+    #[serde(default)]
+    candidates_token_count: u64,
+    #[serde(default)]
+    thoughts_token_count: u64,
+    #[serde(default)]
+    total_token_count: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptFeedback {
+    block_reason: Option<String>,
+}
+
+/// One page of the model list.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModelList {
+    #[serde(default)]
+    models: Vec<ModelInfo>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ModelInfo {
+    name: String,
+}
+
+impl Gemini {
+    /// Create a client from the effective configuration.
+    pub fn new(config: &Config) -> Result<Self, Box<dyn std::error::Error>> {
+        let http = provider::http_client_builder(config)
+            .build()
+            .map_err(|e| format!("failed to build HTTP client: {e}"))?;
+        Ok(Self {
+            http,
+            api_base: config
+                .api_base
+                .clone()
+                .unwrap_or_else(|| DEFAULT_API_BASE.to_string()),
+            api_key: config.api_token()?,
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.http
+            .request(method, provider::endpoint(&self.api_base, path))
+            .header("x-goog-api-key", &self.api_key)
+    }
+}
+
+/// Model id without the `models/` resource prefix.
+fn model_id(name: &str) -> &str {
+    name.strip_prefix("models/").unwrap_or(name)
+}
+
+#[async_trait]
+impl Provider for Gemini {
+    fn name(&self) -> &'static str {
+        "gemini"
+    }
+
+    async fn generate(&self, request: &Request) -> provider::Result<Completion> {
+        if request.params.reasoning_effort.is_some() || request.params.verbosity.is_some() {
+            debug!(
+                "the Gemini API does not support `reasoning_effort` and `verbosity`, ignoring them"
+            );
+        }
+        let body = GenerateRequest {
+            system_instruction: Content {
+                role: None,
+                parts: vec![Part {
+                    text: &request.system,
+                }],
+            },
+            contents: vec![Content {
+                role: Some("user"),
+                parts: vec![Part {
+                    text: &request.prompt,
+                }],
+            }],
+            generation_config: GenerationConfig {
+                temperature: request.params.temperature,
+                top_p: request.params.top_p,
+                max_output_tokens: request.params.max_completion_tokens,
+                seed: request.params.seed,
+                stop_sequences: &request.params.stop,
+            },
+        };
+
+        trace!("✨ Using model: {}", request.model);
+        let path = format!("models/{}:generateContent", model_id(&request.model));
+        let response: GenerateResponse =
+            provider::send_json(self.request(reqwest::Method::POST, &path).json(&body)).await?;
+
+        let text: String = response
+            .candidates
+            .into_iter()
+            .next()
+            .and_then(|c| c.content)
+            .map(|c| {
+                c.parts
+                    .into_iter()
+                    .filter(|p| !p.thought)
+                    .filter_map(|p| p.text)
+                    .collect()
+            })
+            .unwrap_or_default();
+        if text.is_empty() {
+            let reason = response
+                .prompt_feedback
+                .and_then(|f| f.block_reason)
+                .map(|r| format!(" (blocked: {r})"))
+                .unwrap_or_default();
+            return Err(Error::Other(format!("response contained no text{reason}")));
+        }
+
+        // Thinking tokens are billed as output, so count them as completion.
+        let usage = response.usage_metadata.map(|u| Usage {
+            prompt_tokens: u.prompt_token_count,
+            completion_tokens: u.candidates_token_count + u.thoughts_token_count,
+            total_tokens: u.total_token_count,
+            reasoning_tokens: (u.thoughts_token_count > 0).then_some(u.thoughts_token_count),
+        });
+
+        Ok(Completion { text, usage })
+    }
+
+    async fn list_models(&self) -> provider::Result<Vec<String>> {
+        let mut models = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut request = self
+                .request(reqwest::Method::GET, "models")
+                .query(&[("pageSize", "1000")]);
+            if let Some(token) = &page_token {
+                request = request.query(&[("pageToken", token)]);
+            }
+            let page: ModelList = provider::send_json(request).await?;
+            models.extend(page.models.iter().map(|m| model_id(&m.name).to_string()));
+            match page.next_page_token.filter(|t| !t.is_empty()) {
+                Some(token) => page_token = Some(token),
+                None => return Ok(models),
+            }
+        }
+    }
+
+    async fn check(&self, model: &str) -> provider::Result<()> {
+        let model = model_id(model);
+        if self.list_models().await?.iter().any(|m| m == model) {
+            Ok(())
+        } else {
+            Err(Error::Other(format!("model {model} not found")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ProviderKind;
+    use crate::provider::mock::{MockServer, Reply};
+
+    fn config(base: &str) -> Config {
+        Config {
+            provider: ProviderKind::Gemini,
+            api_base: Some(format!("{base}/v1beta")),
+            api_token: "gemini-key".to_string(),
+            ..Config::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn generate_maps_system_instruction_and_usage() {
+        let server = MockServer::start(vec![Reply::json(
+            r#"{"candidates":[{"content":{"role":"model","parts":[
+                    {"text":"thinking...","thought":true},
+                    {"text":"docs: a\n\n- b"}]},"finishReason":"STOP"}],
+                "usageMetadata":{"promptTokenCount":20,"candidatesTokenCount":6,
+                    "thoughtsTokenCount":4,"totalTokenCount":30}}"#,
+        )])
+        .await;
+        let client = Gemini::new(&config(server.url())).unwrap();
+
+        let completion = client
+            .generate(&Request {
+                model: "gemini-2.5-flash".into(),
+                system: "sys".into(),
+                prompt: "diff".into(),
+                ..Request::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(completion.text, "docs: a\n\n- b");
+        assert_eq!(
+            completion.usage,
+            Some(Usage {
+                prompt_tokens: 20,
+                completion_tokens: 10,
+                total_tokens: 30,
+                reasoning_tokens: Some(4),
+            })
+        );
+
+        let sent = &server.requests()[0];
+        assert_eq!(sent.path, "/v1beta/models/gemini-2.5-flash:generateContent");
+        assert_eq!(sent.header("x-goog-api-key"), Some("gemini-key"));
+        let body = sent.json();
+        assert_eq!(body["systemInstruction"]["parts"][0]["text"], "sys");
+        assert_eq!(body["contents"][0]["role"], "user");
+        assert_eq!(body["contents"][0]["parts"][0]["text"], "diff");
+    }
+
+    #[tokio::test]
+    async fn blocked_prompt_is_an_error() {
+        let server = MockServer::start(vec![Reply::json(
+            r#"{"promptFeedback":{"blockReason":"SAFETY"}}"#,
+        )])
+        .await;
+        let client = Gemini::new(&config(server.url())).unwrap();
+
+        let err = client
+            .generate(&Request {
+                model: "gemini-2.5-flash".into(),
+                system: "sys".into(),
+                prompt: "diff".into(),
+                ..Request::default()
+            })
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("SAFETY"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn check_strips_model_resource_prefix() {
+        let server = MockServer::start(vec![Reply::json(
+            r#"{"models":[{"name":"models/gemini-2.5-pro"},{"name":"models/gemini-2.5-flash"}]}"#,
+        )])
+        .await;
+        let client = Gemini::new(&config(server.url())).unwrap();
+
+        assert!(client.check("models/gemini-2.5-flash").await.is_ok());
+        assert!(client.check("gemini-1.0-pro").await.is_err());
+        assert_eq!(server.requests()[0].path, "/v1beta/models?pageSize=1000");
+    }
+
+    #[tokio::test]
+    async fn check_follows_pagination() {
+        let server = MockServer::start(vec![
+            Reply::json(
+                r#"{"models":[{"name":"models/gemini-2.5-pro"}],"nextPageToken":"page-2"}"#,
+            ),
+            Reply::json(r#"{"models":[{"name":"models/gemini-2.5-flash"}]}"#),
+        ])
+        .await;
+        let client = Gemini::new(&config(server.url())).unwrap();
+
+        assert!(client.check("gemini-2.5-flash").await.is_ok());
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[1].path,
+            "/v1beta/models?pageSize=1000&pageToken=page-2"
+        );
+    }
+}
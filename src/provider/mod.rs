@@ -0,0 +1,441 @@
+/*!
+ * Copyright (c) 2026 mingcheng <mingcheng@apache.org>
+ *
+ * This source code is licensed under the MIT License,
+ * which is located in the LICENSE file in the source tree's root directory.
+ *
+ * Model provider abstraction.
+ *
+ * Every backend turns a provider-neutral [`Request`] into its own wire
+ * format and maps the reply back into a [`Completion`]. Message generation
+ * only depends on the [`Provider`] trait, so switching backends is a matter
+ * of configuration.
+ *
+ * File: mod.rs
+ * Author: mingcheng <mingcheng@apache.org>
+ * File Created: 2026-10-16 15:24:41
+ *
+ * Modified By: mingcheng <mingcheng@apache.org>
+ * Last Modified: 2026-10-16 15:24:41
+ */
+
+pub mod anthropic;
+pub mod azure;
+pub mod fallback;
+pub mod gemini;
+#[cfg(test)]
+pub(crate) mod mock;
+pub mod ollama;
+pub mod openai;
+pub mod retry;
+
+use crate::built_info;
+use crate::config::Config;
+use async_openai::types::chat::{ReasoningEffort, Verbosity};
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{ClientBuilder, Proxy, RequestBuilder};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+use tracing::{debug, trace, warn};
+
+/// Result type returned by providers.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Supported provider backends, selected with the `provider` config key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    /// OpenAI and OpenAI-compatible chat completion APIs.
+    #[default]
+    OpenAI,
+    /// Anthropic Messages API.
+    Anthropic,
+    /// Native Ollama API.
+    Ollama,
+    /// Azure OpenAI deployments.
+    Azure,
+    /// Google Gemini `generateContent` API.
+    Gemini,
+}
+
+/// Sampling and generation parameters.
+///
+/// Unset values keep the API defaults. Backends skip the parameters they do
+/// not support.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Params {
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    pub max_completion_tokens: Option<u32>,
+    pub seed: Option<i64>,
+    pub stop: Vec<String>,
+    pub reasoning_effort: Option<ReasoningEffort>,
+    pub verbosity: Option<Verbosity>,
+}
+
+/// A provider-neutral completion request.
+#[derive(Debug, Clone, Default)]
+pub struct Request {
+    pub model: String,
+    pub system: String,
+    pub prompt: String,
+    pub params: Params,
+}
+
+/// Token accounting reported by the API.
+///
+/// `reasoning_tokens` is part of `completion_tokens` and only reported by
+/// reasoning models.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_tokens: Option<u64>,
+}
+
+impl Display for Usage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "completion_tokens: {}, prompt_tokens: {}, total_tokens: {}",
+            self.completion_tokens, self.prompt_tokens, self.total_tokens
+        )?;
+        if let Some(reasoning) = self.reasoning_tokens {
+            write!(f, ", reasoning_tokens: {reasoning}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Generated text and its usage statistics.
+#[derive(Debug, Clone)]
+pub struct Completion {
+    pub text: String,
+    pub usage: Option<Usage>,
+}
+
+/// The provider and model that produced a message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Origin {
+    pub provider: String,
+    pub model: String,
+}
+
+impl Display for Origin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.provider, self.model)
+    }
+}
+
+/// Errors returned by providers.
+#[derive(Debug)]
+pub enum Error {
+    /// The API answered with a non-success HTTP status. `retry_after` holds
+    /// the server's hint from `Retry-After` or rate-limit reset headers.
+    Status {
+        status: u16,
+        message: String,
+        retry_after: Option<Duration>,
+    },
+    /// The API reported an error without exposing the HTTP status.
+    Api(String),
+    /// The request could not be sent or the response not received.
+    Transport(String),
+    /// Unexpected response shape or client-side misconfiguration.
+    Other(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Status {
+                status, message, ..
+            } => write!(f, "HTTP {status}: {message}"),
+            Self::Api(message) => write!(f, "API error: {message}"),
+            Self::Transport(message) => write!(f, "request failed: {message}"),
+            Self::Other(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Error {
+    /// Whether another attempt, possibly against another provider, may
+    /// succeed: timeouts, connection failures, rate limits and server errors.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Status { status, .. } => matches!(status, 408 | 409 | 429) || *status >= 500,
+            Self::Transport(_) => true,
+            Self::Api(_) | Self::Other(_) => false,
+        }
+    }
+
+    /// How long the server asked to wait before the next attempt.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::Status { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_decode() {
+            Self::Other(format!("invalid response body: {e}"))
+        } else {
+            Self::Transport(e.to_string())
+        }
+    }
+}
+
+/// Receives generated text while a completion streams in.
+pub trait Sink: Send + Sync {
+    /// Append a piece of generated text.
+    fn delta(&self, text: &str);
+
+    /// Discard partial output before another attempt starts.
+    fn reset(&self) {}
+}
+
+/// A backend able to generate commit messages.
+#[async_trait]
+pub trait Provider: Send + Sync {
+    /// Short name used in logs, e.g. `openai`.
+    fn name(&self) -> &'static str;
+
+    /// Generate a completion for `request`.
+    async fn generate(&self, request: &Request) -> Result<Completion>;
+
+    /// Generate a completion, passing text to `sink` as it arrives.
+    ///
+    /// Backends without streaming support deliver the whole text at once.
+    async fn generate_stream(&self, request: &Request, sink: &dyn Sink) -> Result<Completion> {
+        let completion = self.generate(request).await?;
+        sink.delta(&completion.text);
+        Ok(completion)
+    }
+
+    /// List the model identifiers available to the configured credentials.
+    async fn list_models(&self) -> Result<Vec<String>>;
+
+    /// Verify the API is reachable and `model` is available.
+    async fn check(&self, model: &str) -> Result<()> {
+        let models = self.list_models().await?;
+        debug!("available models: {models:?}");
+
+        if models.iter().any(|m| m == model) {
+            debug!(
+                "{} API is reachable and model {model} is available",
+                self.name()
+            );
+            Ok(())
+        } else {
+            Err(Error::Other(format!("model {model} not found")))
+        }
+    }
+}
+
+/// Build the provider selected by `config`.
+pub fn build(
+    config: &Config,
+) -> std::result::Result<Box<dyn Provider>, Box<dyn std::error::Error>> {
+    trace!("using provider {:?}", config.provider);
+    Ok(match config.provider {
+        ProviderKind::OpenAI => Box::new(openai::OpenAI::new(config)?),
+        ProviderKind::Anthropic => Box::new(anthropic::Anthropic::new(config)?),
+        ProviderKind::Ollama => Box::new(ollama::Ollama::new(config)?),
+        ProviderKind::Azure => Box::new(azure::Azure::new(config)?),
+        ProviderKind::Gemini => Box::new(gemini::Gemini::new(config)?),
+    })
+}
+
+/// Create an HTTP client builder with the default identification headers,
+/// configured extra headers, proxy and timeout.
+///
+/// Invalid header names or values and unparsable proxy URLs are skipped with
+/// a warning rather than aborting, matching how other settings degrade.
+pub fn http_client_builder(config: &Config) -> ClientBuilder {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "HTTP-Referer",
+        HeaderValue::from_static(built_info::PKG_HOMEPAGE),
+    );
+    headers.insert("X-Title", HeaderValue::from_static(built_info::PKG_NAME));
+    headers.insert("X-Client-Type", HeaderValue::from_static("CLI"));
+
+    for (name, value) in &config.headers {
+        match (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            (Ok(name), Ok(value)) => {
+                headers.insert(name, value);
+            }
+            _ => warn!("ignoring invalid header `{name}`"),
+        }
+    }
+
+    let mut builder = ClientBuilder::new()
+        .user_agent(format!(
+            "{} ({})",
+            built_info::PKG_NAME,
+            built_info::PKG_DESCRIPTION
+        ))
+        .default_headers(headers);
+
+    if let Some(proxy_addr) = config.proxy() {
+        trace!("Using proxy: {proxy_addr}");
+        match Proxy::all(proxy_addr) {
+            Ok(proxy) => builder = builder.proxy(proxy),
+            Err(e) => warn!("ignoring invalid proxy `{proxy_addr}`: {e}"),
+        }
+    }
+
+    // A zero timeout leaves the HTTP client at its default (no timeout)
+    // instead of forcing an immediate-timeout value.
+    if let Some(timeout) = config.timeout() {
+        trace!("Setting request timeout to: {}s", timeout.as_secs());
+        builder = builder.timeout(timeout);
+    } else {
+        trace!("No request timeout configured; using HTTP client default");
+    }
+
+    builder
+}
+
+/// Join a base URL and an endpoint path without doubling slashes.
+pub(crate) fn endpoint(base: &str, path: &str) -> String {
+    format!(
+        "{}/{}",
+        base.trim_end_matches('/'),
+        path.trim_start_matches('/')
+    )
+}
+
+/// Send `request` and decode a JSON response body.
+///
+/// Non-success statuses become [`Error::Status`] with the API's error
+/// message when the body follows the common `{"error": {"message": ...}}`
+/// shape, or the raw body otherwise.
+pub(crate) async fn send_json<T: DeserializeOwned>(request: RequestBuilder) -> Result<T> {
+    let response = request.send().await?;
+    let status = response.status();
+    let retry_after = retry::server_delay(status, response.headers());
+    let body = response.bytes().await?;
+
+    if !status.is_success() {
+        return Err(Error::Status {
+            status: status.as_u16(),
+            message: error_message(&body),
+            retry_after,
+        });
+    }
+
+    serde_json::from_slice(&body).map_err(|e| {
+        Error::Other(format!(
+            "failed to parse response: {e}: {}",
+            String::from_utf8_lossy(&body)
+        ))
+    })
+}
+
+/// Send `request` and pass the payload of every server-sent `data:` line to
+/// `on_data` until the `[DONE]` marker or the end of the stream.
+///
+/// Non-success statuses are reported like [`send_json`] does.
+pub(crate) async fn send_sse(
+    request: RequestBuilder,
+    mut on_data: impl FnMut(&str) -> Result<()>,
+) -> Result<()> {
+    let mut response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        let retry_after = retry::server_delay(status, response.headers());
+        let body = response.bytes().await?;
+        return Err(Error::Status {
+            status: status.as_u16(),
+            message: error_message(&body),
+            retry_after,
+        });
+    }
+
+    let mut buffer = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        buffer.extend_from_slice(&chunk);
+        while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim_end().strip_prefix("data:") else {
+                continue;
+            };
+            let data = data.trim_start();
+            if data == "[DONE]" {
+                return Ok(());
+            }
+            on_data(data)?;
+        }
+    }
+    Ok(())
+}
+
+/// Extract a human-readable message from an error response body.
+pub(crate) fn error_message(body: &[u8]) -> String {
+    let text = String::from_utf8_lossy(body).trim().to_string();
+    let Ok(value) = serde_json::from_slice::<serde_json::Value>(body) else {
+        return text;
+    };
+    let error = value.get("error").unwrap_or(&value);
+    error
+        .get("message")
+        .and_then(|m| m.as_str())
+        .or_else(|| error.as_str())
+        .map(str::to_string)
+        .unwrap_or(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoint_joins_without_double_slashes() {
+        assert_eq!(
+            endpoint("https://api.example.com/v1/", "/models"),
+            "https://api.example.com/v1/models"
+        );
+        assert_eq!(endpoint("http://h", "a/b"), "http://h/a/b");
+    }
+
+    #[test]
+    fn error_message_prefers_api_message() {
+        assert_eq!(
+            error_message(br#"{"error":{"type":"x","message":"bad key"}}"#),
+            "bad key"
+        );
+        assert_eq!(error_message(br#"{"error":"overloaded"}"#), "overloaded");
+        assert_eq!(error_message(b"Bad Gateway\n"), "Bad Gateway");
+    }
+
+    #[test]
+    fn retryable_errors() {
+        let status = |status| Error::Status {
+            status,
+            message: String::new(),
+            retry_after: None,
+        };
+        assert!(status(503).is_retryable());
+        assert!(status(429).is_retryable());
+        assert!(status(408).is_retryable());
+        assert!(!status(401).is_retryable());
+        assert!(!status(404).is_retryable());
+        assert!(Error::Transport("timed out".into()).is_retryable());
+        assert!(!Error::Other("bad json".into()).is_retryable());
+    }
+}
@@ -0,0 +1,460 @@
+/*!
+ * Copyright (c) 2026 mingcheng <mingcheng@apache.org>
+ *
+ * This source code is licensed under the MIT License,
+ * which is located in the LICENSE file in the source tree's root directory.
+ *
+ * Native Ollama backend.
+ *
+ * Talks to `/api/chat` and `/api/tags` instead of the OpenAI-compatible
+ * layer, which drops Ollama-specific options. The context window is sized
+ * from the prompt unless configured, because the server default is small
+ * and silently truncates large diffs from the start.
+ *
+ * File: ollama.rs
+ * Author: mingcheng <mingcheng@apache.org>
+ * File Created: 2026-10-16 16:48:20
+ *
+ * Modified By: mingcheng <mingcheng@apache.org>
+ * Last Modified: 2026-10-16 16:48:20
+ */
+
+use crate::config::Config;
+use crate::provider::{self, Completion, Error, Provider, Request, Usage};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::{debug, info, trace, warn};
+
+/// Default API endpoint when `api_base` is not configured.
+pub const DEFAULT_API_BASE: &str = "http://localhost:11434";
+
+/// Smallest context window requested when sizing it automatically.
+const MIN_NUM_CTX: u64 = 4096;
+
+/// Largest context window requested when sizing it automatically.
+const MAX_NUM_CTX: u64 = 131_072;
+
+/// Tokens reserved for the generated message unless
+/// `max_completion_tokens` is configured.
+const RESPONSE_TOKENS: u64 = 1024;
+
+/// Ollama-specific settings, the `[ollama]` table.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OllamaConfig {
+    /// Context window in tokens. Sized from the prompt when unset.
+    pub num_ctx: Option<u64>,
+    /// How long the model stays loaded after the request, e.g. `10m`.
+    pub keep_alive: Option<String>,
+    /// Pull a missing model during `--check-model` instead of failing.
+    pub pull: bool,
+    /// Extra model options passed through verbatim, e.g. `temperature`.
+    pub options: BTreeMap<String, toml::Value>,
+}
+
+/// Client for the native Ollama API.
+pub struct Ollama {
+    http: reqwest::Client,
+    api_base: String,
+    api_token: String,
+    settings: OllamaConfig,
+}
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<Message<'a>>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<&'a str>,
+    options: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Serialize)]
+struct Message<'a> {
+    role: &'static str,
+    content: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    message: ResponseMessage,
+    #[serde(default)]
+    prompt_eval_count: u64,
+    #[serde(default)]
+    eval_count: u64,
+}
+
+#[derive(Deserialize)]
+struct ResponseMessage {
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct TagList {
+    models: Vec<LocalModel>,
+}
+
+#[derive(Deserialize)]
+struct LocalModel {
+    name: String,
+}
+
+#[derive(Serialize)]
+struct PullRequest<'a> {
+    model: &'a str,
+    stream: bool,
+}
+
+#[derive(Deserialize)]
+struct PullResponse {
+    status: String,
+}
+
+impl Ollama {
+    /// Create a client from the effective configuration.
+    ///
+    /// A token is optional and only sent when configured, for servers
+    /// behind an authenticating proxy.
+    pub fn new(config: &Config) -> Result<Self, Box<dyn std::error::Error>> {
+        let http = provider::http_client_builder(config)
+            .build()
+            .map_err(|e| format!("failed to build HTTP client: {e}"))?;
+        Ok(Self {
+            http,
+            api_base: config
+                .api_base
+                .clone()
+                .unwrap_or_else(|| DEFAULT_API_BASE.to_string()),
+            api_token: config.api_token()?,
+            settings: config.ollama.clone(),
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let builder = self
+            .http
+            .request(method, provider::endpoint(&self.api_base, path));
+        if self.api_token.is_empty() {
+            builder
+        } else {
+            builder.bearer_auth(&self.api_token)
+        }
+    }
+
+    /// Model options sent with every chat request. Entries of
+    /// `[ollama.options]` win over the generic generation parameters.
+    fn options(&self, request: &Request) -> BTreeMap<String, serde_json::Value> {
+        let params = &request.params;
+        let mut options = BTreeMap::new();
+        let mut set = |name: &str, value: Option<serde_json::Value>| {
+            if let Some(value) = value {
+                options.insert(name.to_string(), value);
+            }
+        };
+        set("temperature", params.temperature.map(Into::into));
+        set("top_p", params.top_p.map(Into::into));
+        set("num_predict", params.max_completion_tokens.map(Into::into));
+        set("seed", params.seed.map(Into::into));
+        set(
+            "stop",
+            (!params.stop.is_empty()).then(|| params.stop.clone().into()),
+        );
+        options.extend(
+            self.settings
+                .options
+                .iter()
+                .filter_map(|(k, v)| serde_json::to_value(v).ok().map(|v| (k.clone(), v))),
+        );
+
+        // A `num_ctx` in `[ollama.options]` wins like any other option.
+        let configured = options
+            .get("num_ctx")
+            .and_then(serde_json::Value::as_u64)
+            .or(self.settings.num_ctx)
+            .filter(|n| *n > 0);
+        let needed = estimate_tokens(request);
+        let num_ctx = match configured {
+            Some(configured) => {
+                if configured < needed {
+                    warn!(
+                        "num_ctx = {configured} is smaller than the estimated \
+                         {needed} tokens; the diff may be truncated"
+                    );
+                }
+                configured
+            }
+            None => {
+                if needed > MAX_NUM_CTX {
+                    warn!(
+                        "the request needs about {needed} tokens, but num_ctx is capped at \
+                         {MAX_NUM_CTX}; Ollama may truncate the prompt, set `ollama.num_ctx` \
+                         or lower `max_prompt_tokens`"
+                    );
+                } else if needed < MIN_NUM_CTX {
+                    debug!("raising the estimated {needed} tokens to num_ctx {MIN_NUM_CTX}");
+                }
+                estimate_num_ctx(needed)
+            }
+        };
+        debug!("requesting a context window of {num_ctx} tokens");
+        options.insert("num_ctx".to_string(), num_ctx.into());
+        options
+    }
+
+    async fn pull(&self, model: &str) -> provider::Result<()> {
+        info!("pulling model {model}, this may take a while");
+        let response: PullResponse = provider::send_json(
+            self.request(reqwest::Method::POST, "api/pull")
+                .json(&PullRequest {
+                    model,
+                    stream: false,
+                }),
+        )
+        .await?;
+        if response.status == "success" {
+            Ok(())
+        } else {
+            Err(Error::Other(format!(
+                "pulling {model} ended with status `{}`",
+                response.status
+            )))
+        }
+    }
+}
+
+/// Tokens needed for the prompt and the reply, using the usual four
+/// characters per token.
+fn estimate_tokens(request: &Request) -> u64 {
+    let chars = (request.system.len() + request.prompt.len()) as u64;
+    let response = request
+        .params
+        .max_completion_tokens
+        .map_or(RESPONSE_TOKENS, u64::from);
+    chars.div_ceil(4) + response
+}
+
+/// Context window for `tokens`, rounded up to a power of two and clamped
+/// to [`MIN_NUM_CTX`]..=[`MAX_NUM_CTX`].
+fn estimate_num_ctx(tokens: u64) -> u64 {
+    tokens.next_power_of_two().clamp(MIN_NUM_CTX, MAX_NUM_CTX)
+}
+
+/// Whether a local model name matches the requested one. Ollama names
+/// without a tag refer to `:latest`.
+fn same_model(local: &str, wanted: &str) -> bool {
+    let with_tag = |name: &str| {
+        if name.contains(':') {
+            name.to_string()
+        } else {
+            format!("{name}:latest")
+        }
+    };
+    with_tag(local) == with_tag(wanted)
+}
+
+#[async_trait]
+impl Provider for Ollama {
+    fn name(&self) -> &'static str {
+        "ollama"
+    }
+
+    async fn generate(&self, request: &Request) -> provider::Result<Completion> {
+        let body = ChatRequest {
+            model: &request.model,
+            messages: vec![
+                Message {
+                    role: "system",
+                    content: &request.system,
+                },
+                Message {
+                    role: "user",
+                    content: &request.prompt,
+                },
+            ],
+            stream: false,
+            keep_alive: self.settings.keep_alive.as_deref(),
+            options: self.options(request),
+        };
+
+        trace!("✨ Using model: {}", request.model);
+        let response: ChatResponse =
+            provider::send_json(self.request(reqwest::Method::POST, "api/chat").json(&body))
+                .await?;
+
+        Ok(Completion {
+            text: response.message.content,
+            usage: Some(Usage {
+                prompt_tokens: response.prompt_eval_count,
+                completion_tokens: response.eval_count,
+                total_tokens: response.prompt_eval_count + response.eval_count,
+                reasoning_tokens: None,
+            }),
+        })
+    }
+
+    async fn list_models(&self) -> provider::Result<Vec<String>> {
+        let tags: TagList =
+            provider::send_json(self.request(reqwest::Method::GET, "api/tags")).await?;
+        Ok(tags.models.into_iter().map(|m| m.name).collect())
+    }
+
+    async fn check(&self, model: &str) -> provider::Result<()> {
+        let models = self.list_models().await?;
+        debug!("local models: {models:?}");
+        if models.iter().any(|m| same_model(m, model)) {
+            return Ok(());
+        }
+
+        if self.settings.pull {
+            return self.pull(model).await;
+        }
+        Err(Error::Other(format!(
+            "model {model} is not available locally (found: {}); run `ollama pull {model}` \
+             or set ollama.pull = true",
+            if models.is_empty() {
+                "none".to_string()
+            } else {
+                models.join(", ")
+            }
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::mock::{MockServer, Reply};
+    use crate::provider::{Params, ProviderKind};
+
+    fn config(base: &str, settings: OllamaConfig) -> Config {
+        Config {
+            provider: ProviderKind::Ollama,
+            api_base: Some(base.to_string()),
+            ollama: settings,
+            ..Config::default()
+        }
+    }
+
+    fn request(prompt: &str) -> Request {
+        Request {
+            model: "qwen3:8b".into(),
+            system: "sys".into(),
+            prompt: prompt.into(),
+            ..Request::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn generate_sends_native_chat_options() {
+        let server = MockServer::start(vec![Reply::json(
+            r#"{"model":"qwen3:8b","message":{"role":"assistant","content":"feat: x"},
+                "done":true,"prompt_eval_count":30,"eval_count":4}"#,
+        )])
+        .await;
+        let settings = OllamaConfig {
+            keep_alive: Some("10m".into()),
+            options: BTreeMap::from([("temperature".to_string(), toml::Value::Float(0.2))]),
+            ..OllamaConfig::default()
+        };
+        let client = Ollama::new(&config(server.url(), settings)).unwrap();
+
+        let completion = client.generate(&request("diff")).await.unwrap();
+        assert_eq!(completion.text, "feat: x");
+        assert_eq!(completion.usage.unwrap().total_tokens, 34);
+
+        let sent = &server.requests()[0];
+        assert_eq!(sent.path, "/api/chat");
+        assert!(sent.header("authorization").is_none());
+        let body = sent.json();
+        assert_eq!(body["stream"], false);
+        assert_eq!(body["keep_alive"], "10m");
+        assert_eq!(body["options"]["temperature"], 0.2);
+        assert_eq!(body["options"]["num_ctx"], MIN_NUM_CTX);
+        assert_eq!(body["messages"][1]["content"], "diff");
+    }
+
+    #[test]
+    fn ollama_options_override_generation_params() {
+        let settings = OllamaConfig {
+            options: BTreeMap::from([("temperature".to_string(), toml::Value::Float(0.9))]),
+            ..OllamaConfig::default()
+        };
+        let client = Ollama::new(&config("http://localhost:11434", settings)).unwrap();
+        let mut request = request("diff");
+        request.params = Params {
+            temperature: Some(0.1),
+            max_completion_tokens: Some(256),
+            seed: Some(42),
+            ..Params::default()
+        };
+
+        let options = client.options(&request);
+        assert_eq!(options["temperature"], 0.9);
+        assert_eq!(options["num_predict"], 256);
+        assert_eq!(options["seed"], 42);
+        assert!(!options.contains_key("stop"));
+    }
+
+    #[test]
+    fn num_ctx_grows_with_the_prompt() {
+        let num_ctx = |prompt: &str| estimate_num_ctx(estimate_tokens(&request(prompt)));
+        assert_eq!(num_ctx("small"), MIN_NUM_CTX);
+        assert_eq!(num_ctx(&"x".repeat(100_000)), 32_768);
+        assert_eq!(num_ctx(&"x".repeat(10_000_000)), MAX_NUM_CTX);
+    }
+
+    #[test]
+    fn explicit_num_ctx_wins() {
+        let settings = OllamaConfig {
+            num_ctx: Some(8192),
+            ..OllamaConfig::default()
+        };
+        let client = Ollama::new(&config("http://localhost:11434", settings)).unwrap();
+        assert_eq!(client.options(&request("small"))["num_ctx"], 8192);
+
+        let settings = OllamaConfig {
+            num_ctx: Some(8192),
+            options: BTreeMap::from([("num_ctx".to_string(), toml::Value::Integer(16_384))]),
+            ..OllamaConfig::default()
+        };
+        let client = Ollama::new(&config("http://localhost:11434", settings)).unwrap();
+        assert_eq!(client.options(&request("small"))["num_ctx"], 16_384);
+    }
+
+    #[tokio::test]
+    async fn check_matches_implicit_latest_tag_and_pulls() {
+        let server = MockServer::start(vec![
+            Reply::json(r#"{"models":[{"name":"llama3:latest","model":"llama3:latest"}]}"#),
+            Reply::json(r#"{"models":[]}"#),
+            Reply::json(r#"{"status":"success"}"#),
+        ])
+        .await;
+        let settings = OllamaConfig {
+            pull: true,
+            ..OllamaConfig::default()
+        };
+        let client = Ollama::new(&config(server.url(), settings)).unwrap();
+
+        assert!(client.check("llama3").await.is_ok());
+        assert!(client.check("qwen3:8b").await.is_ok());
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[2].path, "/api/pull");
+        assert_eq!(requests[2].json()["model"], "qwen3:8b");
+    }
+
+    #[tokio::test]
+    async fn check_lists_local_models_when_missing() {
+        let server = MockServer::start(vec![Reply::json(
+            r#"{"models":[{"name":"llama3:latest"},{"name":"qwen3:8b"}]}"#,
+        )])
+        .await;
+        let client = Ollama::new(&config(server.url(), OllamaConfig::default())).unwrap();
+
+        let err = client.check("mistral").await.unwrap_err().to_string();
+        assert!(err.contains("llama3:latest, qwen3:8b"), "{err}");
+    }
+}
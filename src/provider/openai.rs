@@ -0,0 +1,837 @@
+/*
+ * Copyright (c) 2025-2026 mingcheng <mingcheng@apache.org>
+ *
+ * This source code is licensed under the MIT License,
+ * which is located in the LICENSE file in the source tree's root directory.
+ *
+ * File: openai.rs
+ * Author: mingcheng (mingcheng@apache.org)
+ * File Created: 2025-03-01 21:55:58
+ *
+ * Modified By: mingcheng (mingcheng@apache.org)
+ * Last Modified: 2026-10-16 18:40:12
+ */
+
+use crate::built_info;
+use crate::config::Config;
+use crate::provider::{self, Completion, Error, Provider, Request, Sink, Usage};
+use async_openai::config::OPENAI_API_BASE;
+use async_openai::error::OpenAIError;
+use async_openai::types::chat::{
+    ChatCompletionRequestSystemMessageArgs, ChatCompletionRequestUserMessageArgs,
+    ChatCompletionStreamOptions, CompletionUsage, CreateChatCompletionRequest,
+    CreateChatCompletionRequestArgs, CreateChatCompletionResponse, ReasoningEffort,
+    StopConfiguration, Verbosity,
+};
+use async_trait::async_trait;
+use log::{debug, trace};
+use serde::{Deserialize, Serialize};
+
+/// Which OpenAI endpoint generates the message.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Api {
+    /// The Responses API for reasoning models on api.openai.com, chat
+    /// completions everywhere else.
+    #[default]
+    Auto,
+    /// `POST /chat/completions`.
+    Chat,
+    /// `POST /responses`.
+    Responses,
+}
+
+/// OpenAI-specific settings, the `[openai]` table.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OpenAIConfig {
+    /// Endpoint used for generation.
+    pub api: Api,
+}
+
+/// OpenAI and OpenAI-compatible chat completion and Responses APIs.
+///
+/// Requests are sent with reqwest directly, using the async-openai types for
+/// the payloads, because `async_openai::Client` drops the HTTP status that
+/// decides whether a failure is worth retrying elsewhere.
+pub struct OpenAI {
+    http: reqwest::Client,
+    api_base: String,
+    api_token: String,
+    api: Api,
+}
+
+/// One `chat.completion.chunk` event. Only the fields needed to assemble
+/// the message are read, as compatible servers often omit the others.
+#[derive(Deserialize)]
+struct StreamChunk {
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    usage: Option<CompletionUsage>,
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct StreamChoice {
+    delta: StreamDelta,
+}
+
+#[derive(Deserialize)]
+struct StreamDelta {
+    content: Option<String>,
+}
+
+/// Body of `POST /responses`. Responses are not stored server-side, a
+/// commit message is never continued.
+#[derive(Serialize)]
+struct ResponsesRequest<'a> {
+    model: &'a str,
+    instructions: &'a str,
+    input: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_output_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reasoning: Option<ReasoningParam>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<TextParam>,
+    store: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+}
+
+#[derive(Serialize)]
+struct ReasoningParam {
+    effort: ReasoningEffort,
+}
+
+#[derive(Serialize)]
+struct TextParam {
+    verbosity: Verbosity,
+}
+
+#[derive(Deserialize)]
+struct ResponsesResponse {
+    #[serde(default)]
+    output: Vec<OutputItem>,
+    usage: Option<ResponsesUsage>,
+    status: Option<String>,
+    incomplete_details: Option<IncompleteDetails>,
+    error: Option<serde_json::Value>,
+}
+
+/// Output items other than messages, such as reasoning summaries, are
+/// skipped.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum OutputItem {
+    Message {
+        #[serde(default)]
+        content: Vec<OutputContent>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum OutputContent {
+    OutputText {
+        text: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct ResponsesUsage {
+    input_tokens: u64,
+    output_tokens: u64,
+    total_tokens: u64,
+    output_tokens_details: Option<OutputTokensDetails>,
+}
+
+#[derive(Deserialize)]
+struct OutputTokensDetails {
+    #[serde(default)]
+    reasoning_tokens: u64,
+}
+
+#[derive(Deserialize)]
+struct IncompleteDetails {
+    reason: Option<String>,
+}
+
+/// One Responses API stream event. Only text deltas, the final response
+/// and errors matter here.
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum ResponsesEvent {
+    #[serde(rename = "response.output_text.delta")]
+    OutputTextDelta { delta: String },
+    #[serde(rename = "response.completed", alias = "response.incomplete")]
+    Completed { response: ResponsesResponse },
+    #[serde(rename = "response.failed")]
+    Failed { response: ResponsesResponse },
+    #[serde(rename = "error")]
+    Error { message: String },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct ModelList {
+    data: Vec<ModelInfo>,
+}
+
+#[derive(Deserialize)]
+struct ModelInfo {
+    id: String,
+}
+
+impl From<OpenAIError> for Error {
+    fn from(e: OpenAIError) -> Self {
+        match e {
+            OpenAIError::Reqwest(e) => e.into(),
+            OpenAIError::ApiError(e) => Error::Api(e.to_string()),
+            other => Error::Other(other.to_string()),
+        }
+    }
+}
+
+impl OpenAI {
+    /// Create a new OpenAI client instance.
+    /// This function sets up the HTTP client with the API key, base URL, and optional proxy settings
+    /// taken from the effective configuration.
+    ///
+    /// Fails when the API token cannot be resolved, e.g. because the
+    /// configured token command exits with an error.
+    pub fn new(config: &Config) -> Result<Self, Box<dyn std::error::Error>> {
+        // Build the HTTP client with default headers, proxy and timeout
+        let http = provider::http_client_builder(config)
+            .build()
+            .map_err(|e| format!("failed to build HTTP client: {e}"))?;
+
+        Ok(Self {
+            http,
+            api_base: config
+                .api_base
+                .clone()
+                .unwrap_or_else(|| OPENAI_API_BASE.to_string()),
+            api_token: config.api_token()?,
+            api: config.openai.api,
+        })
+    }
+
+    /// Whether `model` is generated through the Responses API.
+    fn uses_responses(&self, model: &str) -> bool {
+        match self.api {
+            Api::Chat => false,
+            Api::Responses => true,
+            Api::Auto => is_official(&self.api_base) && is_reasoning_model(model),
+        }
+    }
+
+    fn responses_request<'a>(&self, request: &'a Request, stream: bool) -> ResponsesRequest<'a> {
+        let params = &request.params;
+        if params.seed.is_some() || !params.stop.is_empty() {
+            debug!("the Responses API does not support `seed` and `stop`, ignoring them");
+        }
+        ResponsesRequest {
+            model: &request.model,
+            instructions: &request.system,
+            input: &request.prompt,
+            temperature: params.temperature,
+            top_p: params.top_p,
+            max_output_tokens: params.max_completion_tokens,
+            reasoning: params
+                .reasoning_effort
+                .clone()
+                .map(|effort| ReasoningParam { effort }),
+            text: params
+                .verbosity
+                .clone()
+                .map(|verbosity| TextParam { verbosity }),
+            store: false,
+            stream,
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.http
+            .request(method, provider::endpoint(&self.api_base, path))
+            .bearer_auth(&self.api_token)
+            .header("OpenAI-Organization", built_info::PKG_NAME)
+    }
+}
+
+#[async_trait]
+impl Provider for OpenAI {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    /// Send a chat message to the OpenAI API and return the response.
+    async fn generate(&self, request: &Request) -> provider::Result<Completion> {
+        if self.uses_responses(&request.model) {
+            trace!("✨ Using model: {} (responses)", request.model);
+            let response: ResponsesResponse = provider::send_json(
+                self.request(reqwest::Method::POST, "responses")
+                    .json(&self.responses_request(request, false)),
+            )
+            .await?;
+            return response_completion(response);
+        }
+
+        let chat_request = chat_request(request)?;
+        trace!("✨ Using model: {}", request.model);
+
+        let response: CreateChatCompletionResponse = provider::send_json(
+            self.request(reqwest::Method::POST, "chat/completions")
+                .json(&chat_request),
+        )
+        .await?;
+        Ok(completion(response))
+    }
+
+    /// Stream the completion as server-sent events, like
+    /// `async_openai::Chat::create_stream` but keeping the HTTP status.
+    async fn generate_stream(
+        &self,
+        request: &Request,
+        sink: &dyn Sink,
+    ) -> provider::Result<Completion> {
+        if self.uses_responses(&request.model) {
+            trace!("✨ Streaming from model: {} (responses)", request.model);
+            return stream_response(
+                self.request(reqwest::Method::POST, "responses")
+                    .json(&self.responses_request(request, true)),
+                sink,
+            )
+            .await;
+        }
+
+        let mut chat_request = chat_request(request)?;
+        enable_streaming(&mut chat_request);
+        trace!("✨ Streaming from model: {}", request.model);
+
+        stream_completion(
+            self.request(reqwest::Method::POST, "chat/completions")
+                .json(&chat_request),
+            sink,
+        )
+        .await
+    }
+
+    async fn list_models(&self) -> provider::Result<Vec<String>> {
+        let list: ModelList =
+            provider::send_json(self.request(reqwest::Method::GET, "models")).await?;
+        Ok(list.data.into_iter().map(|m| m.id).collect())
+    }
+}
+
+/// Whether `api_base` is OpenAI's own API, the only endpoint assumed to
+/// serve `/responses`.
+fn is_official(api_base: &str) -> bool {
+    reqwest::Url::parse(api_base)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host == "api.openai.com"))
+        .unwrap_or(false)
+}
+
+/// Reasoning model families, which get the most out of the Responses API.
+fn is_reasoning_model(model: &str) -> bool {
+    let model = model.rsplit('/').next().unwrap_or(model);
+    model.starts_with("gpt-5")
+        || ["o1", "o3", "o4"]
+            .iter()
+            .any(|family| model == *family || model.starts_with(&format!("{family}-")))
+}
+
+/// Build a chat completion request with the system and user prompts and
+/// the configured generation parameters.
+pub(crate) fn chat_request(request: &Request) -> provider::Result<CreateChatCompletionRequest> {
+    let messages = vec![
+        ChatCompletionRequestSystemMessageArgs::default()
+            .content(request.system.as_str())
+            .build()?
+            .into(),
+        ChatCompletionRequestUserMessageArgs::default()
+            .content(request.prompt.as_str())
+            .build()?
+            .into(),
+    ];
+    let mut chat_request = CreateChatCompletionRequestArgs::default()
+        .model(&request.model)
+        .messages(messages)
+        .build()?;
+    let params = &request.params;
+    chat_request.temperature = params.temperature.map(|t| t as f32);
+    chat_request.top_p = params.top_p.map(|p| p as f32);
+    chat_request.max_completion_tokens = params.max_completion_tokens;
+    // Deprecated by OpenAI, but still honoured by many compatible servers.
+    #[allow(deprecated)]
+    {
+        chat_request.seed = params.seed;
+    }
+    if !params.stop.is_empty() {
+        chat_request.stop = Some(StopConfiguration::StringArray(params.stop.clone()));
+    }
+    chat_request.reasoning_effort = params.reasoning_effort.clone();
+    chat_request.verbosity = params.verbosity.clone();
+    Ok(chat_request)
+}
+
+/// Token usage of a chat completion, including reasoning tokens if any.
+fn chat_usage(usage: CompletionUsage) -> Usage {
+    Usage {
+        prompt_tokens: usage.prompt_tokens.into(),
+        completion_tokens: usage.completion_tokens.into(),
+        total_tokens: usage.total_tokens.into(),
+        reasoning_tokens: usage
+            .completion_tokens_details
+            .and_then(|d| d.reasoning_tokens)
+            .filter(|n| *n > 0)
+            .map(Into::into),
+    }
+}
+
+/// Collect the text of every choice and the token usage of a response.
+pub(crate) fn completion(response: CreateChatCompletionResponse) -> Completion {
+    let result: Vec<String> = response
+        .choices
+        .iter()
+        .filter_map(|choice| choice.message.content.as_ref().map(ToString::to_string))
+        .collect();
+
+    let usage = response.usage.map(chat_usage);
+
+    Completion {
+        text: result.join("\n"),
+        usage,
+    }
+}
+
+/// Ask for a streamed response with a final usage chunk.
+pub(crate) fn enable_streaming(request: &mut CreateChatCompletionRequest) {
+    request.stream = Some(true);
+    request.stream_options = Some(ChatCompletionStreamOptions {
+        include_usage: Some(true),
+        include_obfuscation: None,
+    });
+}
+
+/// Send a streaming chat completion request and assemble the completion,
+/// passing content deltas to `sink` as they arrive.
+pub(crate) async fn stream_completion(
+    request: reqwest::RequestBuilder,
+    sink: &dyn Sink,
+) -> provider::Result<Completion> {
+    let mut text = String::new();
+    let mut usage = None;
+
+    provider::send_sse(request, |data| {
+        let chunk: StreamChunk = serde_json::from_str(data)
+            .map_err(|e| Error::Other(format!("failed to parse stream event: {e}: {data}")))?;
+        if let Some(error) = chunk.error {
+            return Err(Error::Api(error_text(&error)));
+        }
+        // Only the first choice is shown while streaming.
+        if let Some(content) = chunk
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|c| c.delta.content)
+        {
+            sink.delta(&content);
+            text.push_str(&content);
+        }
+        if let Some(u) = chunk.usage {
+            usage = Some(chat_usage(u));
+        }
+        Ok(())
+    })
+    .await?;
+
+    Ok(Completion { text, usage })
+}
+
+/// Collect the output text and token usage of a Responses API response.
+///
+/// An empty answer is an error, explaining why the response is incomplete
+/// when the server says so, e.g. because reasoning used up the token limit.
+fn response_completion(response: ResponsesResponse) -> provider::Result<Completion> {
+    if let Some(error) = response.error.filter(|e| !e.is_null()) {
+        return Err(Error::Api(error_text(&error)));
+    }
+
+    let text: String = response
+        .output
+        .into_iter()
+        .filter_map(|item| match item {
+            OutputItem::Message { content } => Some(content),
+            OutputItem::Other => None,
+        })
+        .flatten()
+        .filter_map(|content| match content {
+            OutputContent::OutputText { text } => Some(text),
+            OutputContent::Other => None,
+        })
+        .collect();
+    if text.is_empty() {
+        let reason = response
+            .incomplete_details
+            .and_then(|d| d.reason)
+            .or(response.status.filter(|s| s != "completed"))
+            .map(|r| format!(" ({r})"))
+            .unwrap_or_default();
+        return Err(Error::Other(format!("response contained no text{reason}")));
+    }
+
+    Ok(Completion {
+        text,
+        usage: response.usage.map(response_usage),
+    })
+}
+
+fn response_usage(usage: ResponsesUsage) -> Usage {
+    Usage {
+        prompt_tokens: usage.input_tokens,
+        completion_tokens: usage.output_tokens,
+        total_tokens: usage.total_tokens,
+        reasoning_tokens: usage
+            .output_tokens_details
+            .map(|d| d.reasoning_tokens)
+            .filter(|n| *n > 0),
+    }
+}
+
+/// Send a streaming Responses API request, passing text deltas to `sink`.
+/// The final `response.completed` event carries the token usage.
+async fn stream_response(
+    request: reqwest::RequestBuilder,
+    sink: &dyn Sink,
+) -> provider::Result<Completion> {
+    let mut text = String::new();
+    let mut usage = None;
+
+    provider::send_sse(request, |data| {
+        let event: ResponsesEvent = serde_json::from_str(data)
+            .map_err(|e| Error::Other(format!("failed to parse stream event: {e}: {data}")))?;
+        match event {
+            ResponsesEvent::OutputTextDelta { delta } => {
+                sink.delta(&delta);
+                text.push_str(&delta);
+            }
+            // Without any delta, the final response explains why.
+            ResponsesEvent::Completed { response } if text.is_empty() => {
+                let completion = response_completion(response)?;
+                sink.delta(&completion.text);
+                text = completion.text;
+                usage = completion.usage;
+            }
+            ResponsesEvent::Completed { response } => {
+                usage = response.usage.map(response_usage);
+            }
+            ResponsesEvent::Failed { response } => {
+                let message = response
+                    .error
+                    .map(|e| error_text(&e))
+                    .unwrap_or_else(|| "response failed".to_string());
+                return Err(Error::Api(message));
+            }
+            ResponsesEvent::Error { message } => return Err(Error::Api(message)),
+            ResponsesEvent::Other => {}
+        }
+        Ok(())
+    })
+    .await?;
+
+    Ok(Completion { text, usage })
+}
+
+/// The `message` of an API error object, or the whole object.
+fn error_text(error: &serde_json::Value) -> String {
+    error
+        .get("message")
+        .and_then(|m| m.as_str())
+        .map_or_else(|| error.to_string(), str::to_string)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::provider::Params;
+    use crate::provider::mock::{MockServer, Reply};
+
+    fn config(base: &str) -> Config {
+        Config {
+            api_base: Some(format!("{base}/v1")),
+            api_token: "sk-test".to_string(),
+            ..Config::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn generate_sends_chat_completion_request() {
+        let server = MockServer::start(vec![Reply::json(
+            r#"{"id":"1","object":"chat.completion","created":0,"model":"gpt-5",
+                "choices":[{"index":0,"finish_reason":"stop",
+                  "message":{"role":"assistant","content":"feat: x\n\n- y"}}],
+                "usage":{"prompt_tokens":10,"completion_tokens":5,"total_tokens":15}}"#,
+        )])
+        .await;
+        let client = OpenAI::new(&config(server.url())).unwrap();
+
+        let completion = client
+            .generate(&Request {
+                model: "gpt-5".into(),
+                system: "sys".into(),
+                prompt: "diff".into(),
+                ..Request::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(completion.text, "feat: x\n\n- y");
+        assert_eq!(completion.usage.unwrap().total_tokens, 15);
+
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/v1/chat/completions");
+        assert_eq!(requests[0].header("authorization"), Some("Bearer sk-test"));
+        let body = requests[0].json();
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert_eq!(body["messages"][1]["content"], "diff");
+    }
+
+    #[tokio::test]
+    async fn check_reports_missing_model() {
+        let server = MockServer::start(vec![Reply::json(
+            r#"{"object":"list","data":[{"id":"gpt-5","object":"model","created":0,"owned_by":"x"}]}"#,
+        )])
+        .await;
+        let client = OpenAI::new(&config(server.url())).unwrap();
+        assert!(client.check("gpt-5").await.is_ok());
+        assert!(client.check("gpt-4").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn gateway_errors_keep_their_status() {
+        let server = MockServer::start(vec![Reply::status(502, "Bad Gateway")]).await;
+        let client = OpenAI::new(&config(server.url())).unwrap();
+
+        let err = client
+            .generate(&Request {
+                model: "gpt-5".into(),
+                system: "sys".into(),
+                prompt: "diff".into(),
+                ..Request::default()
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Status { status: 502, .. }), "{err:?}");
+        assert!(err.is_retryable());
+    }
+
+    #[derive(Default)]
+    struct Collect(std::sync::Mutex<Vec<String>>);
+
+    impl Sink for Collect {
+        fn delta(&self, text: &str) {
+            self.0.lock().unwrap().push(text.to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn generate_stream_assembles_deltas() {
+        let events = [
+            r#"{"choices":[{"index":0,"delta":{"role":"assistant","content":""}}]}"#,
+            r#"{"choices":[{"index":0,"delta":{"content":"feat: x"}}]}"#,
+            r#"{"choices":[{"index":0,"delta":{"content":"\n\n- y"}}]}"#,
+            r#"{"choices":[],"usage":{"prompt_tokens":7,"completion_tokens":3,"total_tokens":10}}"#,
+        ];
+        let body: String = events
+            .iter()
+            .map(|e| format!("data: {e}\r\n\r\n"))
+            .chain(["data: [DONE]\n\n".to_string()])
+            .collect();
+        let server = MockServer::start(vec![Reply::json(&body)]).await;
+        let client = OpenAI::new(&config(server.url())).unwrap();
+        let sink = Collect::default();
+
+        let completion = client
+            .generate_stream(
+                &Request {
+                    model: "gpt-5".into(),
+                    system: "sys".into(),
+                    prompt: "diff".into(),
+                    ..Request::default()
+                },
+                &sink,
+            )
+            .await
+            .unwrap();
+        assert_eq!(completion.text, "feat: x\n\n- y");
+        assert_eq!(completion.usage.unwrap().total_tokens, 10);
+        assert_eq!(sink.0.lock().unwrap().join(""), "feat: x\n\n- y");
+
+        let body = server.requests()[0].json();
+        assert_eq!(body["stream"], true);
+        assert_eq!(body["stream_options"]["include_usage"], true);
+    }
+
+    fn responses_config(base: &str) -> Config {
+        Config {
+            openai: OpenAIConfig {
+                api: Api::Responses,
+            },
+            reasoning_effort: Some(ReasoningEffort::Low),
+            verbosity: Some(Verbosity::Low),
+            ..config(base)
+        }
+    }
+
+    fn reasoning_request() -> Request {
+        Request {
+            model: "gpt-5".into(),
+            system: "sys".into(),
+            prompt: "diff".into(),
+            params: Params {
+                reasoning_effort: Some(ReasoningEffort::Low),
+                verbosity: Some(Verbosity::Low),
+                ..Params::default()
+            },
+        }
+    }
+
+    #[test]
+    fn auto_uses_responses_for_reasoning_models_on_openai() {
+        let client = |base: Option<&str>| {
+            OpenAI::new(&Config {
+                api_base: base.map(str::to_string),
+                ..Config::default()
+            })
+            .unwrap()
+        };
+        let official = client(None);
+        assert!(official.uses_responses("gpt-5-mini"));
+        assert!(official.uses_responses("o3"));
+        assert!(official.uses_responses("o4-mini"));
+        assert!(!official.uses_responses("gpt-4o"));
+        assert!(!official.uses_responses("o1x"));
+        assert!(!client(Some("http://localhost:11434/v1")).uses_responses("gpt-5"));
+    }
+
+    #[tokio::test]
+    async fn generate_uses_responses_api() {
+        let server = MockServer::start(vec![Reply::json(
+            r#"{"id":"resp_1","object":"response","status":"completed",
+                "output":[
+                  {"type":"reasoning","id":"rs_1","summary":[]},
+                  {"type":"message","id":"msg_1","role":"assistant","content":[
+                    {"type":"output_text","text":"fix: a\n\n- b","annotations":[]}]}],
+                "usage":{"input_tokens":40,"output_tokens":30,"total_tokens":70,
+                  "output_tokens_details":{"reasoning_tokens":22}}}"#,
+        )])
+        .await;
+        let client = OpenAI::new(&responses_config(server.url())).unwrap();
+
+        let completion = client.generate(&reasoning_request()).await.unwrap();
+        assert_eq!(completion.text, "fix: a\n\n- b");
+        assert_eq!(
+            completion.usage,
+            Some(Usage {
+                prompt_tokens: 40,
+                completion_tokens: 30,
+                total_tokens: 70,
+                reasoning_tokens: Some(22),
+            })
+        );
+
+        let sent = &server.requests()[0];
+        assert_eq!(sent.path, "/v1/responses");
+        let body = sent.json();
+        assert_eq!(body["instructions"], "sys");
+        assert_eq!(body["input"], "diff");
+        assert_eq!(body["reasoning"]["effort"], "low");
+        assert_eq!(body["text"]["verbosity"], "low");
+        assert_eq!(body["store"], false);
+        assert!(body.get("stream").is_none());
+    }
+
+    #[tokio::test]
+    async fn incomplete_response_without_text_is_an_error() {
+        let server = MockServer::start(vec![Reply::json(
+            r#"{"id":"resp_1","object":"response","status":"incomplete",
+                "incomplete_details":{"reason":"max_output_tokens"},
+                "output":[{"type":"reasoning","id":"rs_1","summary":[]}]}"#,
+        )])
+        .await;
+        let client = OpenAI::new(&responses_config(server.url())).unwrap();
+
+        let err = client.generate(&reasoning_request()).await.unwrap_err();
+        assert!(err.to_string().contains("max_output_tokens"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn generate_stream_reads_responses_events() {
+        let events = [
+            r#"{"type":"response.created","response":{"id":"resp_1","output":[]}}"#,
+            r#"{"type":"response.output_text.delta","item_id":"msg_1","delta":"feat: "}"#,
+            r#"{"type":"response.output_text.delta","item_id":"msg_1","delta":"z"}"#,
+            r#"{"type":"response.completed","response":{"id":"resp_1","status":"completed",
+                "output":[],"usage":{"input_tokens":5,"output_tokens":9,"total_tokens":14,
+                "output_tokens_details":{"reasoning_tokens":6}}}}"#,
+        ];
+        let body: String = events
+            .iter()
+            .map(|e| format!("event: x\ndata: {}\n\n", e.replace('\n', "")))
+            .collect();
+        let server = MockServer::start(vec![Reply::json(&body)]).await;
+        let client = OpenAI::new(&responses_config(server.url())).unwrap();
+        let sink = Collect::default();
+
+        let completion = client
+            .generate_stream(&reasoning_request(), &sink)
+            .await
+            .unwrap();
+        assert_eq!(completion.text, "feat: z");
+        assert_eq!(completion.usage.unwrap().reasoning_tokens, Some(6));
+        assert_eq!(sink.0.lock().unwrap().join(""), "feat: z");
+        assert_eq!(server.requests()[0].json()["stream"], true);
+    }
+
+    #[tokio::test]
+    async fn chat_request_carries_generation_params() {
+        let server = MockServer::start(vec![Reply::json(
+            r#"{"id":"1","object":"chat.completion","created":0,"model":"gpt-5",
+                "choices":[{"index":0,"finish_reason":"stop",
+                  "message":{"role":"assistant","content":"feat: x"}}],
+                "usage":{"prompt_tokens":10,"completion_tokens":8,"total_tokens":18,
+                  "completion_tokens_details":{"reasoning_tokens":5}}}"#,
+        )])
+        .await;
+        let client = OpenAI::new(&config(server.url())).unwrap();
+
+        let mut request = reasoning_request();
+        request.params = Params {
+            temperature: Some(0.5),
+            max_completion_tokens: Some(300),
+            seed: Some(7),
+            stop: vec!["END".into()],
+            ..request.params
+        };
+        let completion = client.generate(&request).await.unwrap();
+        assert_eq!(completion.usage.unwrap().reasoning_tokens, Some(5));
+        let body = server.requests()[0].json();
+        assert_eq!(body["reasoning_effort"], "low");
+        assert_eq!(body["verbosity"], "low");
+        assert_eq!(body["temperature"], 0.5);
+        assert_eq!(body["max_completion_tokens"], 300);
+        assert_eq!(body["seed"], 7);
+        assert_eq!(body["stop"], serde_json::json!(["END"]));
+        assert!(body.get("top_p").is_none());
+    }
+}
@@ -0,0 +1,382 @@
+/*!
+ * Copyright (c) 2026 mingcheng <mingcheng@apache.org>
+ *
+ * This source code is licensed under the MIT License,
+ * which is located in the LICENSE file in the source tree's root directory.
+ *
+ * Retries with jittered exponential backoff.
+ *
+ * Retryable failures (see [`crate::provider::Error::is_retryable`]) are attempted again
+ * after a delay. The server's own hint wins when present: `Retry-After`,
+ * `retry-after-ms`, or on a 429 the OpenAI-style `x-ratelimit-reset-*`
+ * headers.
+ * Otherwise the delay doubles from `initial_delay_ms` up to `max_delay_ms`,
+ * with half of it randomized so parallel hooks do not retry in lockstep.
+ * No retry is scheduled past the `max_elapsed` budget.
+ *
+ * File: retry.rs
+ * Author: mingcheng <mingcheng@apache.org>
+ * File Created: 2026-10-16 19:32:18
+ *
+ * Modified By: mingcheng <mingcheng@apache.org>
+ * Last Modified: 2026-10-16 19:32:18
+ */
+
+use crate::provider::Result;
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::warn;
+
+/// Retry settings, the `[retry]` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryConfig {
+    /// Retries after the first attempt. `0` disables retrying.
+    pub max_retries: u32,
+    /// Delay before the first retry in milliseconds, doubled on each retry.
+    pub initial_delay_ms: u64,
+    /// Upper bound of a computed delay in milliseconds.
+    pub max_delay_ms: u64,
+    /// Total time budget in seconds, measured from the first attempt.
+    /// `0` removes the cap.
+    pub max_elapsed: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_delay_ms: 500,
+            max_delay_ms: 20_000,
+            max_elapsed: 60,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Delay before retry number `retry` (zero-based), preferring the
+    /// server's hint over the computed backoff.
+    fn delay(&self, retry: u32, hint: Option<Duration>) -> Duration {
+        if let Some(hint) = hint {
+            return hint;
+        }
+        let base = self
+            .initial_delay_ms
+            .saturating_mul(1u64 << retry.min(32))
+            .min(self.max_delay_ms);
+        let half = base / 2;
+        Duration::from_millis(half + fastrand::u64(0..=base - half))
+    }
+
+    fn max_elapsed(&self) -> Option<Duration> {
+        (self.max_elapsed > 0).then(|| Duration::from_secs(self.max_elapsed))
+    }
+}
+
+/// Run `attempt` until it succeeds, fails with a non-retryable error, or
+/// the retry count or time budget is exhausted. The last error is returned.
+pub async fn run<T, F, Fut>(policy: &RetryConfig, mut attempt: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let start = Instant::now();
+    let mut retries = 0;
+    loop {
+        let error = match attempt().await {
+            Ok(value) => return Ok(value),
+            Err(e) => e,
+        };
+        if !error.is_retryable() || retries >= policy.max_retries {
+            return Err(error);
+        }
+
+        let delay = policy.delay(retries, error.retry_after());
+        if let Some(budget) = policy.max_elapsed()
+            && start.elapsed() + delay > budget
+        {
+            warn!(
+                "{error}; not retrying, waiting {:.1}s would exceed the {}s budget",
+                delay.as_secs_f64(),
+                budget.as_secs()
+            );
+            return Err(error);
+        }
+
+        retries += 1;
+        warn!(
+            "{error}; retrying in {:.1}s ({retries}/{})",
+            delay.as_secs_f64(),
+            policy.max_retries
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// The delay requested by the server, if any.
+///
+/// `retry-after-ms` and `Retry-After` (seconds or an HTTP date) are exact
+/// instructions. Otherwise a 429 waits for the later of the OpenAI-style
+/// request and token reset times. Those headers come with every response,
+/// so they say nothing about when a failing server recovers.
+pub(crate) fn server_delay(status: StatusCode, headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    if let Some(ms) = header("retry-after-ms").and_then(|v| v.trim().parse::<f64>().ok()) {
+        return Duration::try_from_secs_f64(ms / 1000.0).ok();
+    }
+    if let Some(value) = header("retry-after") {
+        let value = value.trim();
+        if let Ok(secs) = value.parse::<f64>() {
+            return Duration::try_from_secs_f64(secs).ok();
+        }
+        if let Ok(date) = httpdate::parse_http_date(value) {
+            return Some(
+                date.duration_since(SystemTime::now())
+                    .unwrap_or(Duration::ZERO),
+            );
+        }
+    }
+
+    if status != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+    ["x-ratelimit-reset-requests", "x-ratelimit-reset-tokens"]
+        .into_iter()
+        .filter_map(|name| header(name).and_then(parse_reset))
+        .max()
+}
+
+/// Parse reset values such as `1s`, `6m0s`, `250ms` or `1h2m3.5s`. A bare
+/// number is taken as seconds.
+fn parse_reset(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<f64>() {
+        return Duration::try_from_secs_f64(secs).ok();
+    }
+
+    let mut total = 0.0;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .filter(|i| *i > 0)?;
+        let number: f64 = rest[..split].parse().ok()?;
+        rest = &rest[split..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let scale = match &rest[..unit_len] {
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" => 1.0,
+            "ms" => 1e-3,
+            "us" | "µs" => 1e-6,
+            "ns" => 1e-9,
+            _ => return None,
+        };
+        total += number * scale;
+        rest = &rest[unit_len..];
+    }
+    Duration::try_from_secs_f64(total).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::provider::mock::{MockServer, Reply};
+    use crate::provider::openai::OpenAI;
+    use crate::provider::{Error, Provider, Request};
+    use reqwest::header::HeaderValue;
+
+    fn fast() -> RetryConfig {
+        RetryConfig {
+            max_retries: 3,
+            initial_delay_ms: 1,
+            max_delay_ms: 5,
+            max_elapsed: 10,
+        }
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        map
+    }
+
+    #[test]
+    fn parses_server_delays() {
+        assert_eq!(
+            server_delay(
+                StatusCode::TOO_MANY_REQUESTS,
+                &headers(&[("retry-after", "3")])
+            ),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(
+            server_delay(
+                StatusCode::TOO_MANY_REQUESTS,
+                &headers(&[("retry-after-ms", "250"), ("retry-after", "3")])
+            ),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(
+            server_delay(
+                StatusCode::TOO_MANY_REQUESTS,
+                &headers(&[("retry-after", "Wed, 21 Oct 2015 07:28:00 GMT")])
+            ),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(
+            server_delay(
+                StatusCode::TOO_MANY_REQUESTS,
+                &headers(&[
+                    ("x-ratelimit-reset-requests", "1s"),
+                    ("x-ratelimit-reset-tokens", "6m0s"),
+                ])
+            ),
+            Some(Duration::from_secs(360))
+        );
+        assert_eq!(
+            server_delay(
+                StatusCode::SERVICE_UNAVAILABLE,
+                &headers(&[("x-ratelimit-reset-tokens", "6m0s")])
+            ),
+            None
+        );
+        assert_eq!(
+            server_delay(
+                StatusCode::SERVICE_UNAVAILABLE,
+                &headers(&[("retry-after", "3")])
+            ),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(
+            server_delay(StatusCode::TOO_MANY_REQUESTS, &headers(&[])),
+            None
+        );
+    }
+
+    #[test]
+    fn parses_reset_durations() {
+        assert_eq!(parse_reset("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(
+            parse_reset("1h2m3.5s"),
+            Some(Duration::from_secs_f64(3723.5))
+        );
+        assert_eq!(parse_reset("2"), Some(Duration::from_secs(2)));
+        assert_eq!(parse_reset("soon"), None);
+        assert_eq!(parse_reset("5parsecs"), None);
+    }
+
+    #[test]
+    fn backoff_grows_and_is_capped() {
+        let policy = RetryConfig {
+            initial_delay_ms: 100,
+            max_delay_ms: 1000,
+            ..RetryConfig::default()
+        };
+        let first = policy.delay(0, None);
+        assert!((50..=100).contains(&first.as_millis()), "{first:?}");
+        let third = policy.delay(2, None);
+        assert!((200..=400).contains(&third.as_millis()), "{third:?}");
+        let capped = policy.delay(30, None);
+        assert!((500..=1000).contains(&capped.as_millis()), "{capped:?}");
+        assert_eq!(
+            policy.delay(0, Some(Duration::from_secs(7))),
+            Duration::from_secs(7)
+        );
+    }
+
+    async fn generate(server: &MockServer, policy: &RetryConfig) -> Result<String> {
+        let config = Config {
+            api_base: Some(format!("{}/v1", server.url())),
+            api_token: "sk-test".into(),
+            ..Config::default()
+        };
+        let client = OpenAI::new(&config).unwrap();
+        let request = Request {
+            model: "gpt-5".into(),
+            system: "sys".into(),
+            prompt: "diff".into(),
+            ..Request::default()
+        };
+        run(policy, || client.generate(&request))
+            .await
+            .map(|c| c.text)
+    }
+
+    const OK: &str = r#"{"id":"1","object":"chat.completion","created":0,"model":"gpt-5",
+        "choices":[{"index":0,"finish_reason":"stop",
+          "message":{"role":"assistant","content":"feat: ok"}}]}"#;
+
+    #[tokio::test]
+    async fn retries_scripted_failures_until_success() {
+        let server = MockServer::start(vec![
+            Reply::status(429, r#"{"error":{"message":"slow down"}}"#)
+                .header("retry-after-ms", "20"),
+            Reply::status(503, "unavailable"),
+            Reply::json(OK),
+        ])
+        .await;
+
+        let started = Instant::now();
+        assert_eq!(generate(&server, &fast()).await.unwrap(), "feat: ok");
+        assert!(started.elapsed() >= Duration::from_millis(20));
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn ignores_rate_limit_resets_on_server_errors() {
+        let server = MockServer::start(vec![
+            Reply::status(503, "unavailable").header("x-ratelimit-reset-tokens", "6m0s"),
+            Reply::json(OK),
+        ])
+        .await;
+
+        let started = Instant::now();
+        assert_eq!(generate(&server, &fast()).await.unwrap(), "feat: ok");
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_retries() {
+        let server = MockServer::start(vec![Reply::status(500, "boom")]).await;
+        let policy = RetryConfig {
+            max_retries: 2,
+            ..fast()
+        };
+
+        let err = generate(&server, &policy).await.unwrap_err();
+        assert!(matches!(err, Error::Status { status: 500, .. }));
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let server = MockServer::start(vec![Reply::status(400, "bad request")]).await;
+        assert!(generate(&server, &fast()).await.is_err());
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn respects_the_elapsed_budget() {
+        let server = MockServer::start(vec![
+            Reply::status(429, "rate limited").header("retry-after", "30"),
+        ])
+        .await;
+
+        let started = Instant::now();
+        let err = generate(&server, &fast()).await.unwrap_err();
+        assert!(matches!(err, Error::Status { status: 429, .. }));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(server.requests().len(), 1);
+    }
+}
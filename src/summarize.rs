@@ -0,0 +1,277 @@
+/*!
+ * Copyright (c) 2026 mingcheng <mingcheng@apache.org>
+ *
+ * This source code is licensed under the MIT License,
+ * which is located in the LICENSE file in the source tree's root directory.
+ *
+ * Map-reduce summarization of very large staged changes.
+ *
+ * When the diff does not fit the token budget even after trimming hunk
+ * bodies, every large file is summarized in a request of its own and the
+ * summaries replace its hunks in the final prompt. Small files stay
+ * verbatim. Summaries are cached by the file's blob ids, so a re-run only
+ * asks again for files whose staged content changed.
+ *
+ * File: summarize.rs
+ * Author: mingcheng <mingcheng@apache.org>
+ * File Created: 2026-10-16 20:58:12
+ *
+ * Modified By: mingcheng <mingcheng@apache.org>
+ * Last Modified: 2026-10-16 20:58:12
+ */
+
+use crate::budget::Budget;
+use crate::cache::Cache;
+use crate::config::Config;
+use crate::git::diff::{self, FileDiff, Summary, SummaryKind};
+use crate::prompt;
+use crate::provider::fallback::Chain;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use tracing::{debug, info};
+
+/// Files estimated below this many tokens stay verbatim.
+const MIN_FILE_TOKENS: usize = 256;
+
+/// Summarization settings, the `[summarize]` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SummarizeConfig {
+    /// Summarize large files separately when the diff does not fit.
+    pub enabled: bool,
+    /// Summary requests in flight at the same time.
+    pub concurrency: usize,
+}
+
+impl Default for SummarizeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            concurrency: 4,
+        }
+    }
+}
+
+/// Summarizes large files through the provider chain.
+pub struct Summarizer<'a> {
+    chain: &'a Chain,
+    cache: &'a Cache,
+    budget: &'a Budget,
+    config: &'a Config,
+    use_cache: bool,
+}
+
+impl<'a> Summarizer<'a> {
+    pub fn new(
+        chain: &'a Chain,
+        cache: &'a Cache,
+        budget: &'a Budget,
+        config: &'a Config,
+        use_cache: bool,
+    ) -> Self {
+        Self {
+            chain,
+            cache,
+            budget,
+            config,
+            use_cache,
+        }
+    }
+
+    /// Replace the hunks of every large file in `files` with a summary.
+    pub async fn condense(&self, files: &[FileDiff]) -> Result<Vec<FileDiff>, Box<dyn Error>> {
+        let system = prompt::summary()?;
+        let large: Vec<usize> = files
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| {
+                f.shows_hunks()
+                    && self
+                        .budget
+                        .estimate(&diff::render(std::slice::from_ref(f)).join("\n"))
+                        >= MIN_FILE_TOKENS
+            })
+            .map(|(i, _)| i)
+            .collect();
+        let concurrency = self.config.summarize.concurrency.max(1);
+        info!(
+            "summarizing {} large files separately, {concurrency} at a time",
+            large.len()
+        );
+
+        let results: Vec<_> = stream::iter(large)
+            .map(|i| {
+                let system = &system;
+                async move { (i, self.summarize(system, &files[i]).await) }
+            })
+            .buffer_unordered(concurrency)
+            .collect()
+            .await;
+
+        let mut condensed = files.to_vec();
+        for (i, result) in results {
+            let summary =
+                result.map_err(|e| format!("failed to summarize {}: {e}", files[i].path()))?;
+            condensed[i].summary = Some(Summary {
+                kind: SummaryKind::Model,
+                lines: summary
+                    .lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            });
+        }
+        Ok(condensed)
+    }
+
+    /// Summary of one file, from the cache when its blobs are unchanged.
+    async fn summarize(&self, system: &str, file: &FileDiff) -> Result<String, Box<dyn Error>> {
+        // Full blob ids, as abbreviated ones may collide.
+        let identity = [
+            file.old_path.clone(),
+            file.new_path.clone(),
+            file.old_oid.to_string(),
+            file.new_oid.to_string(),
+        ];
+        let key = Cache::build_key(
+            &self.config.model,
+            &self.config.params(),
+            system,
+            &identity,
+            &[],
+        );
+        if self.use_cache
+            && let Some(cached) = self.cache.get(&key)
+        {
+            debug!("reusing cached summary of {}", file.path());
+            return Ok(cached);
+        }
+
+        // A single huge file still has to fit a request of its own.
+        let fitted = self.budget.fit(
+            system,
+            &prompt::Context::default(),
+            std::slice::from_ref(file),
+        )?;
+        let mut prompt = fitted.diff.join("\n");
+        if !fitted.omitted.is_empty() {
+            prompt.push_str("\n\nOmitted to fit the context window:\n");
+            prompt.push_str(&fitted.omitted.join("\n"));
+        }
+        let (completion, origin) = self.chain.generate(system, &prompt, None).await?;
+        debug!("summarized {} with {origin}", file.path());
+
+        let summary = completion.text.trim().to_string();
+        if self.use_cache {
+            self.cache.put(&key, &summary);
+        }
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::diff::{Hunk, Line};
+    use crate::provider::mock::{MockServer, Reply};
+    use crate::provider::retry::RetryConfig;
+
+    fn reply(text: &str) -> Reply {
+        Reply::json(&format!(
+            r#"{{"id":"1","object":"chat.completion","created":0,"model":"m",
+                "choices":[{{"index":0,"finish_reason":"stop",
+                  "message":{{"role":"assistant","content":"{text}"}}}}]}}"#
+        ))
+    }
+
+    fn file(path: &str, old: &str, new: &str, lines: usize) -> FileDiff {
+        let oid = |digit: &str| git2::Oid::from_str(&digit.repeat(40)).unwrap();
+        FileDiff {
+            status: git2::Delta::Modified,
+            old_path: path.into(),
+            new_path: path.into(),
+            old_oid: oid(old),
+            new_oid: oid(new),
+            old_mode: 0o100644,
+            new_mode: 0o100644,
+            binary: false,
+            hunks: vec![Hunk {
+                header: format!("@@ -1,{lines} +1,{lines} @@"),
+                old_start: 1,
+                old_lines: lines as u32,
+                new_start: 1,
+                new_lines: lines as u32,
+                lines: (0..lines)
+                    .map(|i| Line {
+                        origin: '+',
+                        content: format!("let value_{i} = compute(input, {i});"),
+                    })
+                    .collect(),
+            }],
+            summary: None,
+        }
+    }
+
+    fn config(base: &str) -> Config {
+        Config {
+            api_base: Some(format!("{base}/v1")),
+            api_token: "sk-test".into(),
+            retry: RetryConfig {
+                max_retries: 0,
+                ..RetryConfig::default()
+            },
+            ..Config::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn summarizes_large_files_and_caches_by_blob() {
+        let server = MockServer::start(vec![reply("- adds the value table")]).await;
+        let config = config(server.url());
+        let chain = Chain::new(&config).unwrap();
+        let dir =
+            std::env::temp_dir().join(format!("aigitcommit-summarize-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = Cache::new(&dir);
+        let budget = Budget::new(&config);
+        let summarizer = Summarizer::new(&chain, &cache, &budget, &config, true);
+
+        let files = [
+            file("src/table.rs", "1", "2", 200),
+            file("src/small.rs", "3", "4", 2),
+        ];
+        let condensed = summarizer.condense(&files).await.unwrap();
+
+        assert_eq!(
+            diff::render(&condensed[..1]),
+            [
+                "diff --git a/src/table.rs b/src/table.rs",
+                "index 1111111..2222222 100644",
+                "[summary of 201 diff lines]",
+                "- adds the value table",
+            ]
+        );
+        assert_eq!(condensed[1], files[1]);
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(
+            requests[0].json()["messages"][1]["content"]
+                .as_str()
+                .unwrap()
+                .contains("+let value_199")
+        );
+
+        // The second run is served from the cache.
+        assert_eq!(summarizer.condense(&files).await.unwrap(), condensed);
+        assert_eq!(server.requests().len(), 1);
+
+        // Blobs sharing the abbreviated id are still different content.
+        let mut changed = files[0].clone();
+        changed.new_oid = git2::Oid::from_str(&format!("2222222{}", "f".repeat(33))).unwrap();
+        summarizer.condense(&[changed]).await.unwrap();
+        assert_eq!(server.requests().len(), 2, "not served from the cache");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
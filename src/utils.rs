@@ -12,31 +12,22 @@
  * Last Modified: 2026-05-07 11:29:44
  */
 
+use crate::config::{KEYS, Layers, is_secret};
+use crate::git::exclude::Excluded;
 use crate::git::message::GitMessage;
-use crate::git::repository::Repository;
+use crate::provider::{Origin, Sink};
 use std::fs;
-use std::io::Write;
+use std::io::{IsTerminal, Write};
+use toml::Value;
 use tracing::trace;
 
 /// Convenience alias for fallible utility functions in this crate.
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
-/// Environment variables surfaced by `--check-env`.
-const CHECKED_ENV_VARS: &[&str] = &[
-    "OPENAI_API_BASE",
-    "OPENAI_API_TOKEN",
-    "OPENAI_MODEL_NAME",
-    "OPENAI_API_PROXY",
-    "OPENAI_API_TIMEOUT",
-    "AIGITCOMMIT_SIGNOFF",
-];
-
 /// Environment variable helpers.
 pub mod env {
     use std::env;
 
-    use tracing::{debug, warn};
-
     /// Read an environment variable, returning `default` when unset.
     pub fn get(key: &str, default: &str) -> String {
         env::var(key).unwrap_or_else(|_| default.to_string())
@@ -51,36 +42,30 @@ pub fn get_bool(key: &str) -> bool {
             Err(_) => false,
         }
     }
-
-    /// Log whether `var_name` is set, returning the result.
-    pub fn exists(var_name: &str) -> bool {
-        match env::var(var_name) {
-            Ok(value) => {
-                debug!("{} is set to {}", var_name, value);
-                true
-            }
-            Err(_) => {
-                warn!("{} is not set", var_name);
-                false
-            }
-        }
-    }
-}
-
-/// Should the commit be signed off?
-/// True when the CLI flag is set, or when the repository / env opts in.
-pub fn should_signoff(repository: &Repository, cli_signoff: bool) -> bool {
-    cli_signoff || repository.should_signoff()
 }
 
 /// Output format for commit messages.
-#[derive(Debug, PartialEq, Eq)]
+///
+/// Configured through the `output` key as `table`, `json` or `text`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum OutputFormat {
+    #[serde(rename = "text")]
     Stdout,
     Table,
     Json,
 }
 
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Stdout => "text",
+            Self::Table => "table",
+            Self::Json => "json",
+        })
+    }
+}
+
 impl OutputFormat {
     /// Pick a format from CLI flags. `--json` wins over `--no-table`.
     pub fn detect(json: bool, no_table: bool) -> Self {
@@ -92,17 +77,125 @@ pub fn detect(json: bool, no_table: bool) -> Self {
     }
 
     /// Render `message` to stdout in the selected format.
-    pub fn write(&self, message: &GitMessage) -> Result<()> {
+    ///
+    /// JSON output also names the provider and model that generated the
+    /// message, when it did not come from the cache, and the paths excluded
+    /// from the diff.
+    pub fn write(
+        &self,
+        message: &GitMessage,
+        origin: Option<&Origin>,
+        excluded: &[Excluded],
+    ) -> Result<()> {
         let mut out = std::io::stdout().lock();
         match self {
             Self::Stdout => writeln!(out, "{message}")?,
-            Self::Json => writeln!(out, "{}", serde_json::to_string_pretty(message)?)?,
+            Self::Json => {
+                let output = JsonOutput {
+                    message,
+                    origin,
+                    excluded,
+                };
+                writeln!(out, "{}", serde_json::to_string_pretty(&output)?)?
+            }
             Self::Table => print_table(&message.title, &message.content),
         }
         Ok(())
     }
 }
 
+/// Renders streamed text live on the terminal.
+///
+/// Writes to stderr when it is a terminal, otherwise to the controlling
+/// terminal, so progress is visible even inside git hooks that redirect both
+/// streams. The title line is shown in bold.
+pub struct LiveOutput {
+    state: std::sync::Mutex<LiveState>,
+}
+
+struct LiveState {
+    out: Box<dyn Write + Send>,
+    written: bool,
+    in_title: bool,
+}
+
+impl LiveOutput {
+    /// Open the terminal, or return `None` when there is none.
+    pub fn open() -> Option<Self> {
+        let out: Box<dyn Write + Send> = if std::io::stderr().is_terminal() {
+            Box::new(std::io::stderr())
+        } else {
+            Box::new(fs::OpenOptions::new().write(true).open("/dev/tty").ok()?)
+        };
+        Some(Self::new(out))
+    }
+
+    fn new(out: Box<dyn Write + Send>) -> Self {
+        Self {
+            state: std::sync::Mutex::new(LiveState {
+                out,
+                written: false,
+                in_title: true,
+            }),
+        }
+    }
+
+    /// End the live output with a newline, if anything was written.
+    pub fn finish(&self) {
+        if let Ok(mut state) = self.state.lock()
+            && state.written
+        {
+            let _ = writeln!(state.out);
+        }
+    }
+}
+
+impl Sink for LiveOutput {
+    fn delta(&self, text: &str) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        let state = &mut *state;
+        let mut rest = text;
+        if state.in_title {
+            let (title, tail) = match rest.find('\n') {
+                Some(i) => (&rest[..i], Some(&rest[i..])),
+                None => (rest, None),
+            };
+            if !title.is_empty() {
+                let _ = write!(state.out, "\x1b[1m{title}\x1b[0m");
+            }
+            rest = tail.unwrap_or("");
+            state.in_title = tail.is_none();
+        }
+        let _ = write!(state.out, "{rest}");
+        let _ = state.out.flush();
+        state.written |= !text.is_empty();
+    }
+
+    fn reset(&self) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if state.written {
+            let _ = writeln!(state.out, "\n\x1b[2m(retrying)\x1b[0m");
+            state.written = false;
+            state.in_title = true;
+        }
+    }
+}
+
+/// JSON document written by [`OutputFormat::Json`].
+#[derive(serde::Serialize)]
+struct JsonOutput<'a> {
+    #[serde(flatten)]
+    message: &'a GitMessage,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    origin: Option<&'a Origin>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    excluded: &'a [Excluded],
+}
+
 /// Print the commit message in a rounded, wrapped table.
 fn print_table(title: &str, content: &str) {
     let table =
@@ -116,10 +209,66 @@ fn print_table(title: &str, content: &str) {
     println!("{table}");
 }
 
-/// Log presence/absence of every environment variable consulted by the tool.
-pub fn check_env_variables() {
-    for var in CHECKED_ENV_VARS {
-        env::exists(var);
+/// Print every effective configuration value with its source.
+///
+/// Keys that are known but unset are listed too, so the output doubles as a
+/// reference of what can be configured. Profile definitions are omitted; the
+/// values a selected profile contributes show up with the profile as source.
+pub fn print_explain(layers: &Layers) {
+    let mut rows: Vec<[String; 3]> = layers
+        .iter()
+        .filter(|(key, _)| !key.starts_with("profiles."))
+        .map(|(key, entry)| {
+            [
+                key.to_string(),
+                display_value(key, &entry.value),
+                entry.source.to_string(),
+            ]
+        })
+        .collect();
+    for key in KEYS {
+        if layers.get(key.name).is_none() {
+            rows.push([
+                key.name.to_string(),
+                "(not set)".to_string(),
+                "-".to_string(),
+            ]);
+        }
+    }
+    rows.sort();
+
+    let table = tabled::builder::Builder::from_iter(
+        std::iter::once(["Key".to_string(), "Value".to_string(), "Source".to_string()]).chain(rows),
+    )
+    .build()
+    .with(tabled::settings::Style::rounded())
+    .with(tabled::settings::Width::wrap(120))
+    .with(tabled::settings::Alignment::left())
+    .to_string();
+
+    println!("{table}");
+}
+
+/// Render a configuration value for display, masking secrets.
+pub fn display_value(key: &str, value: &Value) -> String {
+    match value {
+        Value::String(s) if is_secret(key) && !s.is_empty() => {
+            let tail: String = s
+                .chars()
+                .rev()
+                .take(4)
+                .collect::<Vec<_>>()
+                .into_iter()
+                .rev()
+                .collect();
+            if s.chars().count() > 8 {
+                format!("****{tail}")
+            } else {
+                "****".to_string()
+            }
+        }
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
     }
 }
 
@@ -179,6 +328,37 @@ pub fn install_hook(path: &str, name: &str, content: &str) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_live_output_highlights_title() {
+        let buffer = Buffer::default();
+        let live = LiveOutput::new(Box::new(buffer.clone()));
+        live.delta("feat: ");
+        live.delta("x\n\n- body");
+        live.reset();
+        live.delta("fix: y");
+        live.finish();
+
+        let written = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(
+            written,
+            "\x1b[1mfeat: \x1b[0m\x1b[1mx\x1b[0m\n\n- body\n\x1b[2m(retrying)\x1b[0m\n\x1b[1mfix: y\x1b[0m\n"
+        );
+    }
 
     #[test]
     fn test_print_table_simple() {
@@ -229,6 +409,21 @@ fn test_get_bool_truthy_and_falsy() {
         assert!(!env::get_bool("AIGC_TEST_BOOL_MISSING"));
     }
 
+    #[test]
+    fn test_display_value_masks_secrets() {
+        let token = Value::String("sk-abcdefghijkl".to_string());
+        assert_eq!(display_value("api_token", &token), "****ijkl");
+        assert_eq!(
+            display_value("profiles.work.api_token", &Value::String("short".into())),
+            "****"
+        );
+        assert_eq!(
+            display_value("model", &Value::String("gpt-5".into())),
+            "gpt-5"
+        );
+        assert_eq!(display_value("timeout", &Value::Integer(30)), "30");
+    }
+
     #[test]
     fn test_output_format_detect() {
         assert_eq!(OutputFormat::detect(true, false), OutputFormat::Json);